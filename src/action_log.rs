@@ -0,0 +1,76 @@
+//! Bounded in-memory history of high-level editing actions, dumped to a
+//! file on request to show how a curve was built.
+
+use crate::Pt;
+use std::collections::VecDeque;
+use std::fmt;
+use std::fmt::Write as _;
+use std::io;
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+#[derive(Clone, Debug)]
+pub(crate) enum Action {
+    Added(Pt),
+    Moved { index: usize, to: Pt },
+    Duplicated(usize),
+    Split(usize),
+    Joined,
+    Closed,
+    Reflected,
+    Cleared,
+    Restored,
+    Opened(String),
+    Animation(bool),
+}
+
+impl fmt::Display for Action {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Action::Added(p) => write!(f, "point added at ({:.1}, {:.1})", p.x, p.y),
+            Action::Moved { index, to } => write!(f, "point {index} dragged to ({:.1}, {:.1})", to.x, to.y),
+            Action::Duplicated(index) => write!(f, "point {index} duplicated"),
+            Action::Split(index) => write!(f, "curve split at point {index}"),
+            Action::Joined => write!(f, "curves joined"),
+            Action::Closed => write!(f, "curve closed"),
+            Action::Reflected => write!(f, "curve reflected into a closed loop"),
+            Action::Cleared => write!(f, "cleared"),
+            Action::Restored => write!(f, "clear undone"),
+            Action::Opened(path) => write!(f, "opened {path}"),
+            Action::Animation(true) => write!(f, "animation started"),
+            Action::Animation(false) => write!(f, "animation paused"),
+        }
+    }
+}
+
+pub(crate) struct ActionLog {
+    start: Instant,
+    capacity: usize,
+    entries: VecDeque<(Duration, Action)>,
+}
+
+impl ActionLog {
+    pub(crate) fn new(capacity: usize) -> Self {
+        Self {
+            start: Instant::now(),
+            capacity,
+            entries: VecDeque::with_capacity(capacity),
+        }
+    }
+
+    /// Records `action`, dropping the oldest entry once the log is full.
+    pub(crate) fn record(&mut self, action: Action) {
+        if self.entries.len() == self.capacity {
+            self.entries.pop_front();
+        }
+        self.entries.push_back((self.start.elapsed(), action));
+    }
+
+    pub(crate) fn write(&self, path: &Path) -> io::Result<()> {
+        let mut out = String::new();
+        for (at, action) in &self.entries {
+            let _ = writeln!(out, "[{:>9.3}s] {action}", at.as_secs_f32());
+        }
+        std::fs::write(path, out)
+    }
+}
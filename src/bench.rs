@@ -0,0 +1,97 @@
+//! Timing harness for the subdivision code, run with `chaikin --bench`.
+//!
+//! Each case is warmed up, then timed over several samples of a batch of
+//! runs sized to take a few milliseconds. Results are printed as CSV, one
+//! row per case, so runs can be diffed or loaded into a spreadsheet.
+
+use crate::{chaikin_step, precompute_iterations, Pt, Subdivision};
+use std::hint::black_box;
+use std::io::{self, Write};
+use std::time::{Duration, Instant};
+
+const POINT_COUNTS: [usize; 5] = [4, 16, 64, 256, 1024];
+const STEP_COUNTS: [usize; 4] = [1, 3, 5, 7];
+const SAMPLES: usize = 15;
+const WARMUP: Duration = Duration::from_millis(50);
+const SAMPLE_TARGET: Duration = Duration::from_millis(5);
+
+/// Times `chaikin_step` and `precompute_iterations` over a grid of point and
+/// step counts and writes the table to `out`.
+pub fn run_benchmarks(out: &mut impl Write) -> io::Result<()> {
+    writeln!(out, "function,points,steps,runs,mean_ns,median_ns,min_ns")?;
+
+    for &n in &POINT_COUNTS {
+        let base = zigzag(n);
+        let stats = measure(|| chaikin_step(black_box(&base), false));
+        stats.write_row(out, "chaikin_step", n, 1)?;
+    }
+
+    let scheme = Subdivision::default();
+    for &n in &POINT_COUNTS {
+        let base = zigzag(n);
+        for &steps in &STEP_COUNTS {
+            let stats = measure(|| precompute_iterations(black_box(&base), &[], steps, false, &scheme));
+            stats.write_row(out, "precompute_iterations", n, steps)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// A deterministic open polyline across the default canvas.
+fn zigzag(n: usize) -> Vec<Pt> {
+    (0..n)
+        .map(|i| Pt {
+            x: 20.0 + 800.0 * i as f32 / n.max(2) as f32,
+            y: if i % 2 == 0 { 200.0 } else { 600.0 },
+        })
+        .collect()
+}
+
+struct Stats {
+    runs: usize,
+    /// Per-run time of each sample, sorted ascending.
+    samples: Vec<Duration>,
+}
+
+impl Stats {
+    fn write_row(&self, out: &mut impl Write, name: &str, points: usize, steps: usize) -> io::Result<()> {
+        let mean = self.samples.iter().sum::<Duration>() / self.samples.len() as u32;
+        writeln!(
+            out,
+            "{name},{points},{steps},{},{},{},{}",
+            self.runs,
+            mean.as_nanos(),
+            self.samples[self.samples.len() / 2].as_nanos(),
+            self.samples[0].as_nanos(),
+        )
+    }
+}
+
+fn measure<T>(mut f: impl FnMut() -> T) -> Stats {
+    // warm up and find how many runs fill one sample
+    let start = Instant::now();
+    let mut warmup_runs = 0u32;
+    while start.elapsed() < WARMUP {
+        black_box(f());
+        warmup_runs += 1;
+    }
+    let per_run = start.elapsed() / warmup_runs;
+    let runs = (SAMPLE_TARGET.as_nanos() / per_run.as_nanos().max(1)).clamp(1, u32::MAX as u128) as u32;
+
+    let mut samples: Vec<Duration> = (0..SAMPLES)
+        .map(|_| {
+            let start = Instant::now();
+            for _ in 0..runs {
+                black_box(f());
+            }
+            start.elapsed() / runs
+        })
+        .collect();
+    samples.sort();
+
+    Stats {
+        runs: runs as usize,
+        samples,
+    }
+}
@@ -0,0 +1,191 @@
+//! Startup configuration.
+//!
+//! Values start from the built-in defaults, are overridden by a
+//! `chaikin.toml` file (looked up in the working directory, then in the user
+//! config directory), and finally by command-line flags. The file accepts a
+//! flat subset of TOML: one `key = value` per line, with `#` comments.
+//! Flags use the same names with dashes, e.g. `--max-steps 5`. Unknown keys
+//! in the file are skipped with a warning; unknown flags are errors.
+
+use crate::{
+    SmoothMode, ANIM_INTERVAL, BREATH_AMPLITUDE, BREATH_FREQUENCY, HEIGHT, MAX_EXPORT_SCALE, MAX_STEPS, MAX_STEPS_LIMIT, MIN_EDGE_LEN, POINT_BUDGET,
+    SHEET_COLUMNS, SNAP_ANGLE, WIDTH,
+};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+const CONFIG_FILE: &str = "chaikin.toml";
+
+#[derive(Clone, Debug)]
+pub struct Config {
+    pub width: f32,
+    pub height: f32,
+    pub max_steps: usize,
+    pub anim_interval: Duration,
+    /// How long each level is shown, indexed by level. Levels without an
+    /// entry use `anim_interval`; extra entries are ignored.
+    pub level_durations: Vec<Duration>,
+    pub point_budget: usize,
+    pub min_edge_len: f32,
+    pub smooth_mode: SmoothMode,
+    pub vsync: bool,
+    /// Level to show and export regardless of the animation.
+    pub pin_level: Option<usize>,
+    /// Angle increment in degrees for Shift-constrained drags.
+    pub snap_angle: f32,
+    /// Largest breathing offset in pixels.
+    pub breath_amplitude: f32,
+    /// Breathing cycles per second.
+    pub breath_frequency: f32,
+    /// Tiles per row in sprite sheet exports.
+    pub sheet_columns: usize,
+    /// Output pixels per canvas pixel in PNG and sprite sheet exports.
+    pub export_scale: u32,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            width: WIDTH,
+            height: HEIGHT,
+            max_steps: MAX_STEPS,
+            anim_interval: ANIM_INTERVAL,
+            level_durations: Vec::new(),
+            point_budget: POINT_BUDGET,
+            min_edge_len: MIN_EDGE_LEN,
+            smooth_mode: SmoothMode::Chaikin,
+            vsync: true,
+            pin_level: None,
+            snap_angle: SNAP_ANGLE,
+            breath_amplitude: BREATH_AMPLITUDE,
+            breath_frequency: BREATH_FREQUENCY,
+            sheet_columns: SHEET_COLUMNS,
+            export_scale: 1,
+        }
+    }
+}
+
+impl Config {
+    /// Builds the configuration from the config file (if any) and `args`,
+    /// which should not include the program name.
+    pub fn load(args: impl IntoIterator<Item = String>) -> Result<Self, String> {
+        let mut config = Self::default();
+        if let Some(path) = find_config_file() {
+            let text = std::fs::read_to_string(&path).map_err(|e| format!("{}: {e}", path.display()))?;
+            config
+                .apply_file(&text)
+                .map_err(|e| format!("{}: {e}", path.display()))?;
+        }
+        config.apply_args(args)?;
+        Ok(config)
+    }
+
+    fn apply_file(&mut self, text: &str) -> Result<(), String> {
+        for (i, line) in text.lines().enumerate() {
+            let line = line.split('#').next().unwrap_or("").trim();
+            if line.is_empty() || line.starts_with('[') {
+                continue;
+            }
+            let (key, value) = line
+                .split_once('=')
+                .ok_or_else(|| format!("line {}: expected `key = value`", i + 1))?;
+            let (key, value) = (key.trim(), value.trim().trim_matches('"'));
+            if !self.set(key, value).map_err(|e| format!("line {}: {e}", i + 1))? {
+                eprintln!("chaikin: ignoring unknown setting `{key}` on line {}", i + 1);
+            }
+        }
+        Ok(())
+    }
+
+    fn apply_args(&mut self, args: impl IntoIterator<Item = String>) -> Result<(), String> {
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            let Some(flag) = arg.strip_prefix("--") else {
+                return Err(format!("unexpected argument `{arg}`"));
+            };
+            if flag == "no-vsync" {
+                self.vsync = false;
+                continue;
+            }
+            let value = args.next().ok_or_else(|| format!("`--{flag}` needs a value"))?;
+            if !self.set(&flag.replace('-', "_"), &value)? {
+                return Err(format!("unknown flag `--{flag}`"));
+            }
+        }
+        Ok(())
+    }
+
+    /// Sets `key` from its textual value; returns `false` for an unknown key.
+    fn set(&mut self, key: &str, value: &str) -> Result<bool, String> {
+        match key {
+            "width" => self.width = parse_positive(key, value)?,
+            "height" => self.height = parse_positive(key, value)?,
+            "max_steps" => match parse(key, value)? {
+                n if n > MAX_STEPS_LIMIT => return Err(format!("`{key}` must be at most {MAX_STEPS_LIMIT}")),
+                n => self.max_steps = n,
+            },
+            "interval" => self.anim_interval = Duration::from_millis(parse(key, value)?),
+            "level_durations" => self.level_durations = parse_durations(key, value)?,
+            "point_budget" => self.point_budget = parse(key, value)?,
+            "min_edge" => self.min_edge_len = parse_positive(key, value)?,
+            "scheme" => self.smooth_mode = parse(key, value)?,
+            "vsync" => self.vsync = parse(key, value)?,
+            "pin_level" => self.pin_level = Some(parse(key, value)?),
+            "snap_angle" => self.snap_angle = parse_positive(key, value)?,
+            "breath_amplitude" => self.breath_amplitude = parse_positive(key, value)?,
+            "breath_frequency" => self.breath_frequency = parse_positive(key, value)?,
+            "sheet_columns" => match parse(key, value)? {
+                0 => return Err(format!("`{key}` must be positive")),
+                n => self.sheet_columns = n,
+            },
+            "export_scale" => match parse(key, value)? {
+                0 => return Err(format!("`{key}` must be positive")),
+                n if n > MAX_EXPORT_SCALE => return Err(format!("`{key}` must be at most {MAX_EXPORT_SCALE}")),
+                n => self.export_scale = n,
+            },
+            _ => return Ok(false),
+        }
+        Ok(true)
+    }
+}
+
+fn parse<T: std::str::FromStr>(key: &str, value: &str) -> Result<T, String> {
+    value
+        .parse()
+        .map_err(|_| format!("invalid value `{value}` for `{key}`"))
+}
+
+fn parse_positive(key: &str, value: &str) -> Result<f32, String> {
+    let v: f32 = parse(key, value)?;
+    if v > 0.0 {
+        Ok(v)
+    } else {
+        Err(format!("`{key}` must be positive"))
+    }
+}
+
+/// Parses a list of milliseconds such as `[1500, 1100, 800]`; the brackets
+/// are optional so the same text works as a flag value.
+fn parse_durations(key: &str, value: &str) -> Result<Vec<Duration>, String> {
+    value
+        .trim_start_matches('[')
+        .trim_end_matches(']')
+        .split(',')
+        .map(str::trim)
+        .filter(|v| !v.is_empty())
+        .map(|v| parse(key, v).map(Duration::from_millis))
+        .collect()
+}
+
+fn find_config_file() -> Option<PathBuf> {
+    let local = PathBuf::from(CONFIG_FILE);
+    if local.is_file() {
+        return Some(local);
+    }
+
+    let config_dir = std::env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| Path::new(&home).join(".config")))?;
+    let path = config_dir.join("chaikin").join(CONFIG_FILE);
+    path.is_file().then_some(path)
+}
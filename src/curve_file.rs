@@ -0,0 +1,124 @@
+//! Plain-text save format for control points.
+//!
+//! One `x y [label]` entry per line, where the optional label is the rest
+//! of the line. A line reading `closed` marks the curve as closed, and lines
+//! starting with `#` are comments. Closed curves are saved without repeating
+//! the first point; a file that does repeat it as the last point, by the
+//! same test the editor uses to see a curve as closed (`ends_meet`), is read
+//! as closed with the repeat dropped.
+
+use crate::{ends_meet, Pt};
+use std::fmt::Write as _;
+use std::io;
+use std::path::Path;
+
+pub(crate) struct CurveFile {
+    pub(crate) points: Vec<Pt>,
+    /// Parallel to `points`.
+    pub(crate) labels: Vec<Option<String>>,
+    pub(crate) closed: bool,
+}
+
+pub(crate) fn save(path: &Path, curve: &CurveFile) -> io::Result<()> {
+    let mut out = String::from("# chaikin curve\n");
+    if curve.closed {
+        out.push_str("closed\n");
+    }
+    for (i, p) in curve.points.iter().enumerate() {
+        match curve.labels.get(i).and_then(Option::as_deref) {
+            Some(label) => {
+                let _ = writeln!(out, "{} {} {label}", p.x, p.y);
+            }
+            None => {
+                let _ = writeln!(out, "{} {}", p.x, p.y);
+            }
+        }
+    }
+    std::fs::write(path, out)
+}
+
+pub(crate) fn load(path: &Path) -> io::Result<CurveFile> {
+    let text = std::fs::read_to_string(path)?;
+    let mut curve = CurveFile {
+        points: Vec::new(),
+        labels: Vec::new(),
+        closed: false,
+    };
+
+    for (i, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        if line == "closed" {
+            curve.closed = true;
+            continue;
+        }
+
+        let Some((p, label)) = parse_point(line) else {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("line {}: expected `x y [label]`, got `{line}`", i + 1),
+            ));
+        };
+        curve.points.push(p);
+        curve.labels.push(label);
+    }
+
+    if ends_meet(&curve.points) {
+        curve.points.pop();
+        curve.labels.pop();
+        curve.closed = true;
+    }
+
+    Ok(curve)
+}
+
+fn parse_point(line: &str) -> Option<(Pt, Option<String>)> {
+    let (x, rest) = line.split_once(char::is_whitespace)?;
+    let rest = rest.trim_start();
+    let (y, label) = match rest.split_once(char::is_whitespace) {
+        Some((y, label)) => (y, Some(label.trim().to_string())),
+        None => (rest, None),
+    };
+
+    let p = Pt {
+        x: x.parse().ok()?,
+        y: y.parse().ok()?,
+    };
+    Some((p, label.filter(|l| !l.is_empty())))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn load_text(name: &str, text: &str) -> CurveFile {
+        let path = std::env::temp_dir().join(format!("chaikin_{}_{name}.txt", std::process::id()));
+        std::fs::write(&path, text).unwrap();
+        let curve = load(&path);
+        let _ = std::fs::remove_file(&path);
+        curve.unwrap()
+    }
+
+    #[test]
+    fn repeated_first_point_reads_as_closed() {
+        let curve = load_text("repeat", "0 0 start\n100 0\n100 100\n0 100\n0 0 again\n");
+        assert!(curve.closed);
+        assert_eq!(curve.points.len(), 4);
+        assert_eq!(curve.labels, [Some("start".to_string()), None, None, None]);
+    }
+
+    #[test]
+    fn open_curve_stays_open() {
+        let curve = load_text("open", "# comment\n0 0\n100 0\n100 100\n0 100\n");
+        assert!(!curve.closed);
+        assert_eq!(curve.points.len(), 4);
+
+        // with only two others, a repeated start is a doubled-back line,
+        // which is also how the editor sees it
+        let curve = load_text("short", "0 0\n100 0\n0 0\n");
+        assert!(!curve.closed);
+        assert_eq!(curve.points.len(), 3);
+    }
+}
@@ -0,0 +1,246 @@
+//! File exporters for the smoothed curve.
+//!
+//! Every format covers the full logical canvas (`width` x `height` of the
+//! window), independent of what is currently on screen. Raster output is
+//! rendered at `ExportOptions::scale` pixels per logical pixel.
+
+use crate::raster::{Canvas, Rgb};
+use crate::{to_svg_path, Pt};
+use std::fmt::Write as _;
+use std::fs::File;
+use std::io::{self, BufWriter};
+use std::path::Path;
+
+const BACKGROUND: Rgb = [18, 18, 18];
+const CURVE_COLOR: Rgb = [0, 255, 0];
+const LINE_WIDTH: f32 = 2.0;
+const CONTROL_COLOR: Rgb = [128, 128, 128];
+const MARKER_COLOR: Rgb = [255, 0, 0];
+const MARKER_RADIUS: f32 = 4.0;
+/// Largest raster export, in pixels (512 MiB of RGBA).
+const MAX_CANVAS_PIXELS: u64 = 1 << 27;
+
+/// Options applied at the export boundary only; the interactive data is
+/// never modified.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct ExportOptions {
+    /// Round every coordinate to a whole pixel for crisp output.
+    pub(crate) align_to_pixels: bool,
+    /// Add the control polygon and its points to SVG exports, in a group
+    /// of their own next to the curve's.
+    pub(crate) control_polygon: bool,
+    /// Output pixels per canvas pixel for PNGs and sprite sheets. Positions
+    /// and line widths scale together, so the image only gets sharper.
+    pub(crate) scale: u32,
+}
+
+impl Default for ExportOptions {
+    fn default() -> Self {
+        Self {
+            align_to_pixels: false,
+            control_polygon: false,
+            scale: 1,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub(crate) enum ExportFormat {
+    Svg,
+    Png,
+    /// Wavefront OBJ polyline at z = 0, with y flipped to point up.
+    Obj,
+}
+
+impl ExportFormat {
+    pub(crate) fn extension(self) -> &'static str {
+        match self {
+            ExportFormat::Svg => "svg",
+            ExportFormat::Png => "png",
+            ExportFormat::Obj => "obj",
+        }
+    }
+}
+
+/// Everything an exporter needs to write one curve.
+pub(crate) struct ExportJob<'a> {
+    pub(crate) poly: &'a [Pt],
+    /// The control polygon `poly` was smoothed from, without a repeated
+    /// closing point; `closed` applies to both.
+    pub(crate) control: &'a [Pt],
+    pub(crate) closed: bool,
+    pub(crate) width: f32,
+    pub(crate) height: f32,
+    pub(crate) options: ExportOptions,
+}
+
+impl ExportJob<'_> {
+    pub(crate) fn write(&self, format: ExportFormat, path: &Path) -> io::Result<()> {
+        let poly = prepared(self.poly, self.options);
+        match format {
+            ExportFormat::Svg => self.write_svg(&poly, path),
+            ExportFormat::Png => self.write_png(path),
+            ExportFormat::Obj => self.write_obj(&poly, path),
+        }
+    }
+
+    fn write_svg(&self, poly: &[Pt], path: &Path) -> io::Result<()> {
+        let d = to_svg_path(poly, self.closed && poly.len() >= 3);
+        let (w, h) = (self.width, self.height);
+        let mut svg = format!(
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{w}\" height=\"{h}\" viewBox=\"0 0 {w} {h}\">\n\
+             \x20 <rect width=\"100%\" height=\"100%\" fill=\"{}\"/>\n",
+            hex(BACKGROUND),
+        );
+        let curve = format!(
+            "<path d=\"{d}\" fill=\"none\" stroke=\"{}\" stroke-width=\"{LINE_WIDTH}\"/>",
+            hex(CURVE_COLOR)
+        );
+
+        if self.options.control_polygon {
+            let control = prepared(self.control, self.options);
+            let d = to_svg_path(&control, self.closed && control.len() >= 3);
+            let _ = writeln!(
+                svg,
+                "  <g id=\"control-polygon\">\n    <path d=\"{d}\" fill=\"none\" stroke=\"{}\" stroke-width=\"1\"/>",
+                hex(CONTROL_COLOR)
+            );
+            for p in &control {
+                let _ = writeln!(
+                    svg,
+                    "    <circle cx=\"{}\" cy=\"{}\" r=\"{MARKER_RADIUS}\" fill=\"none\" stroke=\"{}\"/>",
+                    p.x,
+                    p.y,
+                    hex(MARKER_COLOR)
+                );
+            }
+            let _ = writeln!(svg, "  </g>\n  <g id=\"curve\">\n    {curve}\n  </g>");
+        } else {
+            let _ = writeln!(svg, "  {curve}");
+        }
+        svg.push_str("</svg>\n");
+        std::fs::write(path, svg)
+    }
+
+    fn write_png(&self, path: &Path) -> io::Result<()> {
+        let (w, h) = canvas_size(self.width, self.height, [self.options.scale; 2])?;
+        let mut canvas = Canvas::new(w, h, BACKGROUND);
+        draw_curve(&mut canvas, self.poly, self.closed, Pt { x: 0.0, y: 0.0 }, self.options);
+        write_png(&canvas, path)
+    }
+
+    fn write_obj(&self, poly: &[Pt], path: &Path) -> io::Result<()> {
+        let mut obj = String::from("# chaikin curve\no curve\n");
+        for p in poly {
+            let _ = writeln!(obj, "v {} {} 0", p.x, self.height - p.y);
+        }
+
+        // OBJ indices start at 1; repeating the first closes the loop
+        obj.push('l');
+        for i in 1..=poly.len() {
+            let _ = write!(obj, " {i}");
+        }
+        if self.closed && poly.len() >= 3 {
+            obj.push_str(" 1");
+        }
+        obj.push('\n');
+        std::fs::write(path, obj)
+    }
+}
+
+/// Every subdivision level drawn into one PNG, one canvas-sized tile per
+/// level, left to right and top to bottom.
+pub(crate) struct SpriteSheet<'a> {
+    pub(crate) levels: &'a [Vec<Pt>],
+    pub(crate) closed: bool,
+    /// Size of one tile: the logical canvas.
+    pub(crate) width: f32,
+    pub(crate) height: f32,
+    pub(crate) columns: usize,
+    pub(crate) options: ExportOptions,
+}
+
+impl SpriteSheet<'_> {
+    /// Writes the sheet and returns its size in pixels.
+    pub(crate) fn write(&self, path: &Path) -> io::Result<(u32, u32)> {
+        let columns = self.columns.clamp(1, self.levels.len().max(1));
+        let rows = self.levels.len().div_ceil(columns);
+        let scale = self.options.scale;
+        let tiles = [columns, rows].map(|n| u32::try_from(n).unwrap_or(u32::MAX).saturating_mul(scale));
+        let (w, h) = canvas_size(self.width, self.height, tiles)?;
+        let mut canvas = Canvas::new(w, h, BACKGROUND);
+
+        for (i, level) in self.levels.iter().enumerate() {
+            let offset = Pt {
+                x: (i % columns) as f32 * self.width,
+                y: (i / columns) as f32 * self.height,
+            };
+            draw_curve(&mut canvas, level, self.closed, offset, self.options);
+        }
+
+        write_png(&canvas, path)?;
+        Ok((canvas.width(), canvas.height()))
+    }
+}
+
+/// The pixel size of a raster `width` x `height` canvas scaled by
+/// `factors`, refused when it overflows or exceeds `MAX_CANVAS_PIXELS`.
+fn canvas_size(width: f32, height: f32, factors: [u32; 2]) -> io::Result<(u32, u32)> {
+    let w = (width as u32).checked_mul(factors[0]);
+    let h = (height as u32).checked_mul(factors[1]);
+    match (w, h) {
+        (Some(w), Some(h)) if u64::from(w) * u64::from(h) <= MAX_CANVAS_PIXELS => Ok((w, h)),
+        _ => Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "image too large: lower the export scale or the sprite sheet size",
+        )),
+    }
+}
+
+/// Rasterizes `poly`, shifted by `offset` canvas pixels, at `options.scale`.
+/// Pixel alignment rounds to output pixels, after scaling.
+fn draw_curve(canvas: &mut Canvas, poly: &[Pt], closed: bool, offset: Pt, options: ExportOptions) {
+    let scale = options.scale as f32;
+    let placed: Vec<Pt> = poly
+        .iter()
+        .map(|p| Pt {
+            x: (p.x + offset.x) * scale,
+            y: (p.y + offset.y) * scale,
+        })
+        .collect();
+    let poly = prepared(&placed, options);
+    let width = LINE_WIDTH * scale;
+    for w in poly.windows(2) {
+        canvas.draw_line(w[0], w[1], width, CURVE_COLOR);
+    }
+    if closed && poly.len() >= 3 {
+        canvas.draw_line(*poly.last().unwrap(), poly[0], width, CURVE_COLOR);
+    }
+}
+
+fn prepared(poly: &[Pt], options: ExportOptions) -> Vec<Pt> {
+    if options.align_to_pixels {
+        poly.iter()
+            .map(|p| Pt {
+                x: p.x.round(),
+                y: p.y.round(),
+            })
+            .collect()
+    } else {
+        poly.to_vec()
+    }
+}
+
+fn hex(c: Rgb) -> String {
+    format!("#{:02x}{:02x}{:02x}", c[0], c[1], c[2])
+}
+
+fn write_png(canvas: &Canvas, path: &Path) -> io::Result<()> {
+    let file = BufWriter::new(File::create(path)?);
+    let mut encoder = png::Encoder::new(file, canvas.width(), canvas.height());
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+    let mut writer = encoder.write_header()?;
+    writer.write_image_data(canvas.pixels())?;
+    Ok(())
+}
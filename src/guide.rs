@@ -0,0 +1,171 @@
+//! Reference polylines imported for tracing.
+//!
+//! A guide is only drawn, never subdivided or edited. It is read from an SVG
+//! file, taking every `<path>`'s `d` attribute with absolute or relative
+//! `M`, `L`, `H`, `V` and `Z` commands (what `to_svg_path` writes), or from a
+//! JSON file, whose numbers are taken in order as `x, y` pairs, so both
+//! `[[x, y], ...]` and `[{"x": x, "y": y}, ...]` work.
+
+use crate::Pt;
+use std::io;
+use std::path::Path;
+
+pub(crate) struct Guide {
+    /// Each subpath with whether it is closed.
+    pub(crate) paths: Vec<(Vec<Pt>, bool)>,
+}
+
+pub(crate) fn load(path: &Path) -> io::Result<Guide> {
+    let text = std::fs::read_to_string(path)?;
+    let is_json = path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("json"));
+    let paths = if is_json { parse_json(&text) } else { parse_svg(&text) }
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+    if paths.iter().all(|(points, _)| points.len() < 2) {
+        return Err(io::Error::new(io::ErrorKind::InvalidData, "no polyline with two or more points"));
+    }
+    Ok(Guide { paths })
+}
+
+fn parse_json(text: &str) -> Result<Vec<(Vec<Pt>, bool)>, String> {
+    let numbers = Tokens::new(text).filter_map(|t| match t {
+        Token::Number(v) => Some(v),
+        Token::Command(_) => None,
+    });
+    let numbers: Vec<f32> = numbers.collect();
+    if !numbers.len().is_multiple_of(2) {
+        return Err("odd number of coordinates".to_string());
+    }
+    let points = numbers.chunks(2).map(|c| Pt { x: c[0], y: c[1] }).collect();
+    Ok(vec![(points, false)])
+}
+
+fn parse_svg(text: &str) -> Result<Vec<(Vec<Pt>, bool)>, String> {
+    let mut paths = Vec::new();
+    let mut rest = text;
+    while let Some(start) = rest.find(" d=\"") {
+        let after = &rest[start + 4..];
+        let end = after.find('"').ok_or("unterminated `d` attribute")?;
+        parse_path_data(&after[..end], &mut paths)?;
+        rest = &after[end..];
+    }
+    if paths.is_empty() {
+        return Err("no <path> data found".to_string());
+    }
+    Ok(paths)
+}
+
+fn parse_path_data(d: &str, paths: &mut Vec<(Vec<Pt>, bool)>) -> Result<(), String> {
+    let mut tokens = Tokens::new(d).peekable();
+    let mut cur = Pt { x: 0.0, y: 0.0 };
+    let mut cmd = None;
+
+    while let Some(token) = tokens.peek().copied() {
+        if let Token::Command(c) = token {
+            tokens.next();
+            cmd = Some(c);
+            if c.eq_ignore_ascii_case(&'z') {
+                if let Some((points, closed)) = paths.last_mut() {
+                    *closed = true;
+                    if let Some(&first) = points.first() {
+                        cur = first;
+                    }
+                }
+                continue;
+            }
+        }
+
+        let c = cmd.ok_or("path data must start with a command")?;
+        let relative = c.is_ascii_lowercase();
+        let (dx, dy) = if relative { (cur.x, cur.y) } else { (0.0, 0.0) };
+        cur = match c.to_ascii_uppercase() {
+            'M' | 'L' => Pt {
+                x: dx + number(&mut tokens)?,
+                y: dy + number(&mut tokens)?,
+            },
+            'H' => Pt {
+                x: dx + number(&mut tokens)?,
+                y: cur.y,
+            },
+            'V' => Pt {
+                x: cur.x,
+                y: dy + number(&mut tokens)?,
+            },
+            'Z' => return Err("coordinates after `Z`".to_string()),
+            other => return Err(format!("unsupported path command `{other}`")),
+        };
+
+        if c.eq_ignore_ascii_case(&'m') {
+            paths.push((vec![cur], false));
+            // further pairs after a moveto are linetos
+            cmd = Some(if relative { 'l' } else { 'L' });
+        } else {
+            match paths.last_mut() {
+                Some((points, false)) => points.push(cur),
+                _ => return Err("line before the first `M`".to_string()),
+            }
+        }
+    }
+    Ok(())
+}
+
+fn number(tokens: &mut impl Iterator<Item = Token>) -> Result<f32, String> {
+    match tokens.next() {
+        Some(Token::Number(v)) => Ok(v),
+        _ => Err("expected a number in path data".to_string()),
+    }
+}
+
+#[derive(Clone, Copy)]
+enum Token {
+    Command(char),
+    Number(f32),
+}
+
+/// Splits text into numbers and single-letter commands, skipping anything
+/// else (separators, brackets, keys).
+struct Tokens<'a> {
+    text: &'a str,
+}
+
+impl<'a> Tokens<'a> {
+    fn new(text: &'a str) -> Self {
+        Self { text }
+    }
+}
+
+impl Iterator for Tokens<'_> {
+    type Item = Token;
+
+    fn next(&mut self) -> Option<Token> {
+        loop {
+            let mut chars = self.text.char_indices();
+            let (_, c) = chars.next()?;
+            if c.is_ascii_digit() || c == '-' || c == '+' || c == '.' {
+                let mut end = self.text.len();
+                let mut prev = c;
+                for (i, ch) in chars {
+                    let part_of_number = ch.is_ascii_digit()
+                        || ch == '.'
+                        || ch == 'e'
+                        || ch == 'E'
+                        || ((ch == '-' || ch == '+') && (prev == 'e' || prev == 'E'));
+                    if !part_of_number {
+                        end = i;
+                        break;
+                    }
+                    prev = ch;
+                }
+                let (number, rest) = self.text.split_at(end);
+                self.text = rest;
+                if let Ok(v) = number.parse() {
+                    return Some(Token::Number(v));
+                }
+            } else {
+                self.text = &self.text[c.len_utf8()..];
+                if c.is_ascii_alphabetic() {
+                    return Some(Token::Command(c));
+                }
+            }
+        }
+    }
+}
@@ -1,23 +1,87 @@
 use speedy2d::{
     color::Color,
     dimen::Vector2,
-    window::{MouseButton, VirtualKeyCode, WindowHandler, WindowHelper},
+    font::{Font, TextLayout, TextOptions},
+    shape::Polygon,
+    window::{ModifiersState, MouseButton, MouseScrollDistance, VirtualKeyCode, WindowHandler, WindowHelper},
     Graphics2D,
 };
+use std::borrow::Cow;
+use std::fmt::Write as _;
+use std::path::{Path, PathBuf};
 use std::time::{Duration, Instant};
 
+mod action_log;
+mod bench;
+mod config;
+mod curve_file;
+mod export;
+mod guide;
+mod raster;
+
+pub use bench::run_benchmarks;
+pub use config::Config;
+
+use action_log::{Action, ActionLog};
+use curve_file::CurveFile;
+use export::{ExportFormat, ExportJob, ExportOptions, SpriteSheet};
+use guide::Guide;
+
 pub const WIDTH: f32 = 860.0;
 pub const HEIGHT: f32 = 800.0;
 const MAX_STEPS: usize = 7;
+/// Each level roughly doubles the point count, so deeper settings would
+/// stall the first redraw.
+const MAX_STEPS_LIMIT: usize = 12;
 const CLICK_RADIUS: f32 = 10.0;
 const POINT_OUTER_R: f32 = 5.0;
 const POINT_INNER_R: f32 = 3.5;
 const ANIM_INTERVAL: Duration = Duration::from_millis(1100);
+const DOT_RADIUS: f32 = 1.5;
+const DOT_RADIUS_MIN: f32 = 0.5;
+const DOT_RADIUS_MAX: f32 = 6.0;
+const DOT_RADIUS_STEP: f32 = 0.5;
+const MIN_EDGE_LEN: f32 = 12.0;
+const MIN_EDGE_LEN_MIN: f32 = 2.0;
+const MIN_EDGE_LEN_MAX: f32 = 100.0;
+const MIN_EDGE_LEN_STEP: f32 = 2.0;
+const CURVE_FILE: &str = "chaikin_curve.txt";
+const ACTION_LOG_FILE: &str = "chaikin_actions.log";
+const SPRITE_SHEET_FILE: &str = "chaikin_sheet.png";
+const SHEET_COLUMNS: usize = 4;
+const MAX_EXPORT_SCALE: u32 = 4;
+const GUIDE_FILES: [&str; 2] = ["chaikin_guide.svg", "chaikin_guide.json"];
+const GUIDE_DASH: f32 = 6.0;
+const GUIDE_DASH_PERIOD: f32 = 10.0;
+const ACTION_LOG_CAPACITY: usize = 1000;
+const POINT_BUDGET: usize = 4096;
+const POINT_BUDGET_MIN: usize = 64;
+const POINT_BUDGET_MAX: usize = 1 << 20;
+const HUD_FONT_SIZE: f32 = 14.0;
+const HUD_MARGIN: f32 = 10.0;
+const ARROW_LEN: f32 = 9.0;
+const ARROW_SPREAD: f32 = 0.45;
+const HUD_LINE_HEIGHT: f32 = 18.0;
+const LABEL_OFFSET: Vector2<f32> = Vector2::new(8.0, -18.0);
+const COORD_LABEL_OFFSET: Vector2<f32> = Vector2::new(8.0, 4.0);
+const COORD_FONT_SIZE: f32 = 11.0;
+const SNAP_ANGLE: f32 = 15.0;
+const WEIGHT_MIN: f32 = 0.1;
+const WEIGHT_MAX: f32 = 10.0;
+const WEIGHT_FACTOR: f32 = 1.25;
+const BREATH_AMPLITUDE: f32 = 6.0;
+const BREATH_FREQUENCY: f32 = 0.25;
+const BOUNDS_FLASH: Duration = Duration::from_millis(700);
+const BOUNDS_FLASH_RADIUS: f32 = 12.0;
+const LABEL_PLACEMENT_TRIES: usize = 24;
+const LABEL_SPIRAL_STEP: f32 = 8.0;
+const EMPTY_HINT: &str = "left-click to add points, Enter to animate (needs two)";
+static HUD_FONT: &[u8] = include_bytes!("../assets/DejaVuSansMono.ttf");
 
 #[derive(Clone, Copy, Debug)]
-struct Pt {
-    x: f32,
-    y: f32,
+pub struct Pt {
+    pub x: f32,
+    pub y: f32,
 }
 
 impl From<Vector2<f32>> for Pt {
@@ -37,6 +101,17 @@ fn dist2(a: Pt, b: Pt) -> f32 {
     dx * dx + dy * dy
 }
 
+/// Reflects `p` across the line through `a` and `b`, which must be distinct.
+fn reflect_across(p: Pt, a: Pt, b: Pt) -> Pt {
+    let (dx, dy) = (b.x - a.x, b.y - a.y);
+    let (vx, vy) = (p.x - a.x, p.y - a.y);
+    let t = (vx * dx + vy * dy) / (dx * dx + dy * dy);
+    Pt {
+        x: a.x + 2.0 * t * dx - vx,
+        y: a.y + 2.0 * t * dy - vy,
+    }
+}
+
 fn chaikin_step(points: &[Pt], closed: bool) -> Vec<Pt> {
     let n = points.len();
     if n < 2 {
@@ -76,113 +151,2186 @@ fn chaikin_step(points: &[Pt], closed: bool) -> Vec<Pt> {
     out
 }
 
-fn precompute_iterations(base: &[Pt], max_steps: usize, mut closed: bool) -> Vec<Vec<Pt>> {
-    if base.len() >= 3 && dist2(base[0], *base.last().unwrap()) <= CLICK_RADIUS * CLICK_RADIUS {
-        closed = true;
+/// Variant of `chaikin_step` that leaves edges shorter than `min_len` as-is
+/// and only cuts the longer ones, giving more uniform point spacing on curves
+/// that mix long and short edges.
+fn chaikin_step_min_edge(points: &[Pt], closed: bool, min_len: f32) -> Vec<Pt> {
+    let n = points.len();
+    if n < 2 {
+        return points.to_vec();
+    }
+
+    let min2 = min_len * min_len;
+    let mut out: Vec<Pt> = Vec::with_capacity(n * 2 + 2);
+    let push_unique = |out: &mut Vec<Pt>, p: Pt| {
+        if out.last().is_none_or(|q| dist2(*q, p) > 0.0) {
+            out.push(p);
+        }
+    };
+
+    if !closed {
+        out.push(points[0]);
+    }
+
+    let edges = if closed { n } else { n - 1 };
+    for i in 0..edges {
+        let (p0, p1) = (points[i], points[(i + 1) % n]);
+        if dist2(p0, p1) < min2 {
+            push_unique(&mut out, p0);
+            push_unique(&mut out, p1);
+        } else {
+            out.push(Pt {
+                x: p0.x * 0.75 + p1.x * 0.25,
+                y: p0.y * 0.75 + p1.y * 0.25,
+            });
+            out.push(Pt {
+                x: p0.x * 0.25 + p1.x * 0.75,
+                y: p0.y * 0.25 + p1.y * 0.75,
+            });
+        }
+    }
+
+    if closed {
+        // a short wrap-around edge re-emits the first point
+        if out.len() > 1 && dist2(out[0], *out.last().unwrap()) == 0.0 {
+            out.pop();
+        }
+    } else {
+        push_unique(&mut out, points[n - 1]);
+    }
+
+    out
+}
+
+/// Number of edges in the polyline, counting the closing edge if `closed`.
+fn segment_count(poly: &[Pt], closed: bool) -> usize {
+    match poly.len() {
+        0 | 1 => 0,
+        n if closed && n >= 3 => n,
+        n => n - 1,
+    }
+}
+
+/// Whether the stored points describe a closed curve: the last point
+/// repeats the first, to within `CLICK_RADIUS`, after at least three
+/// others. The editor and the curve file loader both go by this.
+fn ends_meet(points: &[Pt]) -> bool {
+    points.len() >= 4 && dist2(points[0], *points.last().unwrap()) <= CLICK_RADIUS * CLICK_RADIUS
+}
+
+/// The polyline's edges in order, ending with the closing edge if `closed`.
+fn edges(poly: &[Pt], closed: bool) -> impl Iterator<Item = (Pt, Pt)> + '_ {
+    let closing = (closed && poly.len() >= 3).then(|| (*poly.last().unwrap(), poly[0]));
+    poly.windows(2).map(|w| (w[0], w[1])).chain(closing)
+}
+
+/// Total arc length of the polyline, including the closing edge if `closed`.
+fn polyline_length(poly: &[Pt], closed: bool) -> f32 {
+    edges(poly, closed).map(|(a, b)| dist2(a, b).sqrt()).sum()
+}
+
+/// Fully saturated colour for hue `h`, where 0 and 1 are both red.
+fn hue_color(h: f32) -> Color {
+    let h = h.rem_euclid(1.0) * 6.0;
+    let x = 1.0 - (h % 2.0 - 1.0).abs();
+    let (r, g, b) = match h as u32 {
+        0 => (1.0, x, 0.0),
+        1 => (x, 1.0, 0.0),
+        2 => (0.0, 1.0, x),
+        3 => (0.0, x, 1.0),
+        4 => (x, 0.0, 1.0),
+        _ => (1.0, 0.0, x),
+    };
+    Color::from_rgb(r, g, b)
+}
+
+/// One colour per edge of the polyline (see `edges`), picked from the hue
+/// of the edge's midpoint along the curve. Closed curves use the whole hue
+/// circle so the colours meet seamlessly; open ones stop short of it so the
+/// end stays distinguishable from the start.
+fn arc_length_colors(poly: &[Pt], closed: bool) -> Vec<Color> {
+    let total = polyline_length(poly, closed).max(f32::EPSILON);
+    let span = if closed && poly.len() >= 3 { 1.0 } else { 0.8 };
+    let mut travelled = 0.0;
+    edges(poly, closed)
+        .map(|(a, b)| {
+            let len = dist2(a, b).sqrt();
+            let t = (travelled + len / 2.0) / total;
+            travelled += len;
+            hue_color(t * span)
+        })
+        .collect()
+}
+
+/// Rotates `p` about `anchor` to the nearest multiple of `step_deg` degrees,
+/// keeping its distance.
+fn snap_to_angle(anchor: Pt, p: Pt, step_deg: f32) -> Pt {
+    let (dx, dy) = (p.x - anchor.x, p.y - anchor.y);
+    let step = step_deg.to_radians();
+    let angle = (dy.atan2(dx) / step).round() * step;
+    let r = dx.hypot(dy);
+    Pt {
+        x: anchor.x + r * angle.cos(),
+        y: anchor.y + r * angle.sin(),
+    }
+}
+
+/// Greedy label placement for `(preferred top-left, size)` boxes: in order,
+/// each label takes the first spot on a short spiral around its preferred
+/// position that misses every label placed before it, or the preferred spot
+/// if none does. Quadratic in the label count, which is plenty for
+/// hand-placed points.
+fn place_labels(labels: &[(Vector2<f32>, Vector2<f32>)]) -> Vec<Vector2<f32>> {
+    const GOLDEN_ANGLE: f32 = 2.399_963;
+    let mut placed: Vec<(Vector2<f32>, Vector2<f32>)> = Vec::with_capacity(labels.len());
+    for &(preferred, size) in labels {
+        let free = |pos: Vector2<f32>| {
+            placed.iter().all(|&(q, q_size)| {
+                pos.x >= q.x + q_size.x || q.x >= pos.x + size.x || pos.y >= q.y + q_size.y || q.y >= pos.y + size.y
+            })
+        };
+        let pos = (0..LABEL_PLACEMENT_TRIES)
+            .map(|k| {
+                let (r, a) = (LABEL_SPIRAL_STEP * (k as f32).sqrt(), GOLDEN_ANGLE * k as f32);
+                preferred + Vector2::new(r * a.cos(), r * a.sin())
+            })
+            .find(|&pos| free(pos))
+            .unwrap_or(preferred);
+        placed.push((pos, size));
+    }
+    placed.into_iter().map(|(pos, _)| pos).collect()
+}
+
+/// Returns the SVG path data (`d` attribute) for the polyline: an `M` to the
+/// first point, `L` to each following one, and a trailing `Z` if `closed`.
+/// An empty polyline gives an empty string.
+pub fn to_svg_path(poly: &[Pt], closed: bool) -> String {
+    let mut d = String::new();
+    for (i, p) in poly.iter().enumerate() {
+        let cmd = if i == 0 { 'M' } else { 'L' };
+        if i > 0 {
+            d.push(' ');
+        }
+        let _ = write!(d, "{cmd} {} {}", p.x, p.y);
+    }
+    if closed && !poly.is_empty() {
+        d.push_str(" Z");
+    }
+    d
+}
+
+/// The point of segment `ab` nearest to `p`.
+fn closest_point_on_segment(p: Pt, a: Pt, b: Pt) -> Pt {
+    let (dx, dy) = (b.x - a.x, b.y - a.y);
+    let len2 = dx * dx + dy * dy;
+    if len2 <= 0.0 {
+        return a;
+    }
+    let t = (((p.x - a.x) * dx + (p.y - a.y) * dy) / len2).clamp(0.0, 1.0);
+    Pt {
+        x: a.x + dx * t,
+        y: a.y + dy * t,
+    }
+}
+
+/// Returns the point at normalized arc length `t` along the polyline, so
+/// `t = 0` is the first point and `t = 1` the last (or, for a closed
+/// polyline of three or more points, the first again after the closing
+/// edge; like `edges`, two points have no closing edge). `t` is clamped to
+/// `[0, 1]`.
+///
+/// # Panics
+///
+/// Panics if `poly` is empty.
+pub fn sample_at(poly: &[Pt], t: f32, closed: bool) -> Pt {
+    let total = polyline_length(poly, closed);
+    let mut remaining = t.clamp(0.0, 1.0) * total;
+
+    let mut end = poly[0];
+    for (a, b) in edges(poly, closed) {
+        let len = dist2(a, b).sqrt();
+        if remaining <= len && len > 0.0 {
+            let f = remaining / len;
+            return Pt {
+                x: a.x + (b.x - a.x) * f,
+                y: a.y + (b.y - a.y) * f,
+            };
+        }
+        remaining -= len;
+        end = b;
+    }
+    end
+}
+
+/// Signed area of the triangle `a b c`, doubled: positive when
+/// counter-clockwise.
+fn cross(a: Pt, b: Pt, c: Pt) -> f32 {
+    (b.x - a.x) * (c.y - a.y) - (b.y - a.y) * (c.x - a.x)
+}
+
+/// True when segments `ab` and `cd` cross at a single interior point.
+/// Segments that merely touch at an endpoint do not count.
+fn segments_cross(a: Pt, b: Pt, c: Pt, d: Pt) -> bool {
+    let (d1, d2) = (cross(c, d, a), cross(c, d, b));
+    let (d3, d4) = (cross(a, b, c), cross(a, b, d));
+    d1 * d2 < 0.0 && d3 * d4 < 0.0
+}
+
+/// Returns every pair of non-adjacent edges `(i, j)` of the polygon that
+/// cross, where edge `i` runs from `poly[i]` to the next point. Checks all
+/// pairs, which is fine for control polygons.
+fn self_intersections(poly: &[Pt], closed: bool) -> Vec<(usize, usize)> {
+    let n = poly.len();
+    let edges = if closed { n } else { n.saturating_sub(1) };
+    let edge = |i: usize| (poly[i], poly[(i + 1) % n]);
+
+    let mut hits = Vec::new();
+    for i in 0..edges {
+        for j in i + 2..edges {
+            if closed && i == 0 && j == edges - 1 {
+                continue; // they share poly[0]
+            }
+            let ((a, b), (c, d)) = (edge(i), edge(j));
+            if segments_cross(a, b, c, d) {
+                hits.push((i, j));
+            }
+        }
+    }
+    hits
+}
+
+/// Keeps every k-th point (and always the last) so that roughly `budget`
+/// points remain. Rendering only; exports use the full level.
+fn decimate(poly: &[Pt], budget: usize) -> Cow<'_, [Pt]> {
+    if budget < 2 || poly.len() <= budget {
+        return Cow::Borrowed(poly);
+    }
+
+    let k = poly.len().div_ceil(budget);
+    let mut out: Vec<Pt> = poly.iter().step_by(k).copied().collect();
+    if !(poly.len() - 1).is_multiple_of(k) {
+        out.push(*poly.last().unwrap());
+    }
+    Cow::Owned(out)
+}
+
+fn midpoint(a: Pt, b: Pt) -> Pt {
+    Pt {
+        x: (a.x + b.x) * 0.5,
+        y: (a.y + b.y) * 0.5,
+    }
+}
+
+/// One step of the cubic scheme: insert the midpoint of every edge, then
+/// move each original vertex to a 1/4, 1/2, 1/4 average of itself and its
+/// neighbouring midpoints. Open curves keep their endpoints.
+fn cubic_step(points: &[Pt], closed: bool) -> Vec<Pt> {
+    let n = points.len();
+    if n < 2 {
+        return points.to_vec();
+    }
+
+    let smooth = |prev_mid: Pt, p: Pt, next_mid: Pt| Pt {
+        x: prev_mid.x * 0.25 + p.x * 0.5 + next_mid.x * 0.25,
+        y: prev_mid.y * 0.25 + p.y * 0.5 + next_mid.y * 0.25,
+    };
+
+    let mut out = Vec::with_capacity(n * 2);
+
+    if closed && n >= 3 {
+        for i in 0..n {
+            let (prev, p, next) = (points[(i + n - 1) % n], points[i], points[(i + 1) % n]);
+            out.push(smooth(midpoint(prev, p), p, midpoint(p, next)));
+            out.push(midpoint(p, next));
+        }
+    } else {
+        out.push(points[0]);
+        for i in 1..n {
+            out.push(midpoint(points[i - 1], points[i]));
+            if i < n - 1 {
+                let p = points[i];
+                out.push(smooth(midpoint(points[i - 1], p), p, midpoint(p, points[i + 1])));
+            }
+        }
+        out.push(points[n - 1]);
+    }
+
+    out
+}
+
+/// Marks which points of `chaikin_step`'s output depend on a point flagged
+/// in `mask`, its input-sized counterpart.
+fn chaikin_step_mask(mask: &[bool], closed: bool) -> Vec<bool> {
+    let n = mask.len();
+    if n < 2 {
+        return mask.to_vec();
     }
+    let mut out = Vec::with_capacity(n * 2 + 2);
+    if closed {
+        for i in 0..n {
+            let m = mask[i] || mask[(i + 1) % n];
+            out.extend([m, m]);
+        }
+    } else {
+        out.push(mask[0]);
+        for w in mask.windows(2) {
+            let m = w[0] || w[1];
+            out.extend([m, m]);
+        }
+        out.push(mask[n - 1]);
+    }
+    out
+}
 
-    let mut cur = if closed && base.len() >= 2 && dist2(base[0], *base.last().unwrap()) <= CLICK_RADIUS * CLICK_RADIUS {
-        base[..base.len() - 1].to_vec()
+/// `chaikin_step_mask` for `cubic_step`.
+fn cubic_step_mask(mask: &[bool], closed: bool) -> Vec<bool> {
+    let n = mask.len();
+    if n < 2 {
+        return mask.to_vec();
+    }
+    let mut out = Vec::with_capacity(n * 2);
+    if closed && n >= 3 {
+        for i in 0..n {
+            let (prev, m, next) = (mask[(i + n - 1) % n], mask[i], mask[(i + 1) % n]);
+            out.extend([prev || m || next, m || next]);
+        }
     } else {
-        base.to_vec()
+        out.push(mask[0]);
+        for i in 1..n {
+            out.push(mask[i - 1] || mask[i]);
+            if i < n - 1 {
+                out.push(mask[i - 1] || mask[i] || mask[i + 1]);
+            }
+        }
+        out.push(mask[n - 1]);
+    }
+    out
+}
+
+/// Corner-cutting scheme used to refine the control polygon.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum SmoothMode {
+    /// Chaikin's 1/4, 3/4 corner cutting, converging to a quadratic B-spline.
+    #[default]
+    Chaikin,
+    /// Midpoint insertion plus vertex averaging (see `cubic_step`),
+    /// converging to a smoother cubic B-spline that sits further inside the
+    /// control polygon.
+    Cubic,
+}
+
+impl SmoothMode {
+    fn next(self) -> Self {
+        match self {
+            SmoothMode::Chaikin => SmoothMode::Cubic,
+            SmoothMode::Cubic => SmoothMode::Chaikin,
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            SmoothMode::Chaikin => "chaikin",
+            SmoothMode::Cubic => "cubic",
+        }
+    }
+}
+
+impl std::str::FromStr for SmoothMode {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "chaikin" => Ok(SmoothMode::Chaikin),
+            "cubic" => Ok(SmoothMode::Cubic),
+            _ => Err(()),
+        }
+    }
+}
+
+/// Everything that decides how one subdivision step refines a polygon.
+#[derive(Clone, Copy, Debug, Default)]
+struct Subdivision {
+    mode: SmoothMode,
+    /// Short-edge threshold; only honoured by the Chaikin scheme.
+    min_edge: Option<f32>,
+    /// Treat the ends of an open polygon as tangent handles (see
+    /// `with_end_handles`).
+    end_handles: bool,
+}
+
+impl Subdivision {
+    fn step(&self, points: &[Pt], closed: bool) -> Vec<Pt> {
+        match (self.mode, self.min_edge) {
+            (SmoothMode::Chaikin, Some(min_len)) => chaikin_step_min_edge(points, closed, min_len),
+            (SmoothMode::Chaikin, None) => chaikin_step(points, closed),
+            (SmoothMode::Cubic, _) => cubic_step(points, closed),
+        }
+    }
+
+    /// Follows `step` for a mask of points, flagging the output points that
+    /// depend on flagged inputs. The short-edge variant decides its stencil
+    /// from the geometry, so it has no fixed local support and gives `None`.
+    fn step_mask(&self, mask: &[bool], closed: bool) -> Option<Vec<bool>> {
+        match (self.mode, self.min_edge) {
+            (SmoothMode::Chaikin, Some(_)) => None,
+            (SmoothMode::Chaikin, None) => Some(chaikin_step_mask(mask, closed)),
+            (SmoothMode::Cubic, _) => Some(cubic_step_mask(mask, closed)),
+        }
+    }
+}
+
+/// Rewrites an open polygon whose first and last points are handles rather
+/// than curve points. The curve then runs from `p1` to `p(n-2)`, leaving `p1`
+/// in the direction `p0 -> p1` and arriving at `p(n-2)` from the direction of
+/// `p(n-1)`; the farther a handle is, the longer the curve follows it. This
+/// is done by replacing each handle with its reflection through the
+/// neighbouring point, placed just inside the end, so with four points the
+/// result is the control polygon of a Bezier-like segment.
+fn with_end_handles(points: &[Pt]) -> Vec<Pt> {
+    let n = points.len();
+    if n < 4 {
+        return points.to_vec();
+    }
+    let pull = |handle: Pt, end: Pt| Pt {
+        x: 2.0 * end.x - handle.x,
+        y: 2.0 * end.y - handle.y,
     };
+    let mut out = Vec::with_capacity(n);
+    out.push(points[1]);
+    out.push(pull(points[0], points[1]));
+    out.extend_from_slice(&points[2..n - 2]);
+    out.push(pull(points[n - 1], points[n - 2]));
+    out.push(points[n - 2]);
+    out
+}
+
+/// Subdivides `base` `max_steps` times, returning every level from the
+/// base polygon down.
+///
+/// `base` is the control polygon without a repeated closing point, and
+/// `closed` is taken as given (for three or more points): closure is the
+/// caller's call, made on the real points, so a perturbed copy such as
+/// the breathing one cannot close or open the curve by itself.
+///
+/// `weights`, parallel to `base` or empty for all ones, make the scheme
+/// rational: each point is lifted to `(w x, w y, w)`, the linear stencil is
+/// applied to those homogeneous coordinates, and the result is projected
+/// back by dividing by the refined weight. A heavier point pulls the curve
+/// towards itself, a lighter one lets it go; with equal weights the
+/// division cancels and the plain scheme comes out. The short-edge Chaikin
+/// variant picks its stencil from edge lengths, which are meaningless in
+/// homogeneous coordinates, so it ignores the weights.
+fn precompute_iterations(
+    base: &[Pt],
+    weights: &[f32],
+    max_steps: usize,
+    closed: bool,
+    scheme: &Subdivision,
+) -> Vec<Vec<Pt>> {
+    let closed = closed && base.len() >= 3;
+    let mut cur = base.to_vec();
+    let mut weights = weights.get(..cur.len()).unwrap_or_default().to_vec();
+    if scheme.end_handles && !closed {
+        if weights.len() >= 4 {
+            // each handle's replacement keeps the handle's weight
+            let n = weights.len();
+            let (w0, w1, wl, wk) = (weights[0], weights[1], weights[n - 1], weights[n - 2]);
+            weights[..2].copy_from_slice(&[w1, w0]);
+            weights[n - 2..].copy_from_slice(&[wl, wk]);
+        }
+        cur = with_end_handles(&cur);
+    }
 
     let mut iters = Vec::with_capacity(max_steps + 1);
     iters.push(cur.clone());
 
+    let weighted = weights.iter().any(|&w| w != 1.0) && !(scheme.mode == SmoothMode::Chaikin && scheme.min_edge.is_some());
+    if weighted {
+        let mut lifted: Vec<Pt> = cur.iter().zip(&weights).map(|(p, &w)| Pt { x: p.x * w, y: p.y * w }).collect();
+        let mut w: Vec<Pt> = weights.iter().map(|&w| Pt { x: w, y: 0.0 }).collect();
+        for _ in 0..max_steps {
+            lifted = scheme.step(&lifted, closed);
+            w = scheme.step(&w, closed);
+            iters.push(lifted.iter().zip(&w).map(|(p, w)| Pt { x: p.x / w.x, y: p.y / w.x }).collect());
+        }
+        return iters;
+    }
+
     for _ in 0..max_steps {
-        cur = chaikin_step(&cur, closed);
+        cur = scheme.step(&cur, closed);
         iters.push(cur.clone());
     }
 
     iters
 }
 
+/// How often `on_draw` asks for the next frame.
+///
+/// speedy2d only lets vsync be chosen when the window is created (see the
+/// `--no-vsync` flag), so the runtime control is the redraw cadence: when
+/// capped, each frame sleeps until its slot before requesting the next one.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum FramePacing {
+    Uncapped,
+    Fps(u32),
+}
+
+impl FramePacing {
+    fn next(self) -> Self {
+        match self {
+            FramePacing::Uncapped => FramePacing::Fps(60),
+            FramePacing::Fps(60) => FramePacing::Fps(30),
+            FramePacing::Fps(30) => FramePacing::Fps(15),
+            FramePacing::Fps(_) => FramePacing::Uncapped,
+        }
+    }
+
+    fn frame_interval(self) -> Option<Duration> {
+        match self {
+            FramePacing::Uncapped => None,
+            FramePacing::Fps(fps) => Some(Duration::from_secs(1) / fps.max(1)),
+        }
+    }
+}
+
+/// How a closed curve is drawn; open curves are always outlined.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+enum CurveStyle {
+    #[default]
+    Outline,
+    Filled,
+    FilledOutline,
+}
+
+impl CurveStyle {
+    fn next(self) -> Self {
+        match self {
+            CurveStyle::Outline => CurveStyle::Filled,
+            CurveStyle::Filled => CurveStyle::FilledOutline,
+            CurveStyle::FilledOutline => CurveStyle::Outline,
+        }
+    }
+
+    fn filled(self) -> bool {
+        self != CurveStyle::Outline
+    }
+
+    fn outlined(self) -> bool {
+        self != CurveStyle::Filled
+    }
+}
+
+/// Symmetry axis for live mirroring, through the middle of the canvas.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+enum MirrorAxis {
+    #[default]
+    Vertical,
+    Horizontal,
+}
+
+impl MirrorAxis {
+    fn next(self) -> Self {
+        match self {
+            MirrorAxis::Vertical => MirrorAxis::Horizontal,
+            MirrorAxis::Horizontal => MirrorAxis::Vertical,
+        }
+    }
+}
+
+/// How a point added in live-mirror mode is tied to the axis.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum MirrorPair {
+    /// A single point that stays on the axis.
+    OnAxis,
+    /// One of the two points holding this id; each mirrors the other.
+    Pair(u32),
+}
+
+/// How consecutive segments of the smoothed curve meet.
+///
+/// speedy2d draws each line as a separate quad with square ends and has no
+/// cap or join options, so `Butt` is what the backend gives and `Round` is
+/// faked by filling a disc of the line's width at every vertex.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+enum LineJoin {
+    #[default]
+    Round,
+    Butt,
+}
+
+impl LineJoin {
+    fn next(self) -> Self {
+        match self {
+            LineJoin::Round => LineJoin::Butt,
+            LineJoin::Butt => LineJoin::Round,
+        }
+    }
+}
+
+/// Runtime options toggled from the keyboard.
+#[derive(Clone, Debug)]
+struct Settings {
+    /// Draw the finished curve (see `finished_level`) as dots at its
+    /// vertices instead of lines.
+    dots: bool,
+    dot_radius: f32,
+    smooth_mode: SmoothMode,
+    /// Leave edges shorter than `min_edge_len` unsubdivided.
+    skip_short_edges: bool,
+    min_edge_len: f32,
+    export: ExportOptions,
+    show_hud: bool,
+    /// Above this many points the displayed level is drawn decimated.
+    point_budget: usize,
+    frame_pacing: FramePacing,
+    /// Draw all levels stacked instead of the animated one.
+    onion_skin: bool,
+    /// Look for crossing edges in the control polygon after every edit.
+    detect_intersections: bool,
+    /// Label every control point with its `(x, y)` position.
+    show_coordinates: bool,
+    /// Hold the animation on its current level while a point is dragged.
+    freeze_while_dragging: bool,
+    /// Draw the control polygon with arrowheads showing point order.
+    show_arrows: bool,
+    /// Keep markers, labels and the control polygon visible while animating.
+    markers_during_anim: bool,
+    /// Place new points on the finished curve, inserted into the control
+    /// polygon where they refine it (see `insert_on_curve`).
+    snap_to_curve: bool,
+    /// Overlay the polygon through the control edges' midpoints.
+    show_tangent_polygon: bool,
+    line_join: LineJoin,
+    /// Colour the curve by position along it instead of plain green.
+    gradient: bool,
+    /// Always show (and export) this level instead of the animated one.
+    pinned_level: Option<usize>,
+    /// In open curves, use the first and last points as tangent handles.
+    end_handles: bool,
+    /// Add and move points in mirrored pairs (see `add_mirrored`).
+    mirror: bool,
+    mirror_axis: MirrorAxis,
+    /// Angle increment, in degrees, for Shift-constrained drags.
+    snap_angle: f32,
+    /// While dragging, highlight the part of the curve the point controls.
+    ripple_preview: bool,
+    curve_style: CurveStyle,
+    show_guide: bool,
+    /// Smooth the gently moving copy made by `breathing_points` instead of
+    /// the control points themselves.
+    breathing: bool,
+    /// Largest offset of a breathing point, in pixels.
+    breath_amplitude: f32,
+    /// Breathing cycles per second.
+    breath_frequency: f32,
+    /// After every edit, move control points that ended up off the canvas
+    /// back onto its edge (see `constrain_to_bounds`).
+    constrain_to_bounds: bool,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            dots: false,
+            dot_radius: DOT_RADIUS,
+            smooth_mode: SmoothMode::Chaikin,
+            skip_short_edges: false,
+            min_edge_len: MIN_EDGE_LEN,
+            export: ExportOptions::default(),
+            show_hud: true,
+            point_budget: POINT_BUDGET,
+            frame_pacing: FramePacing::Uncapped,
+            onion_skin: false,
+            detect_intersections: false,
+            show_coordinates: false,
+            freeze_while_dragging: false,
+            show_arrows: false,
+            markers_during_anim: true,
+            snap_to_curve: false,
+            show_tangent_polygon: false,
+            line_join: LineJoin::default(),
+            gradient: false,
+            pinned_level: None,
+            end_handles: false,
+            mirror: false,
+            mirror_axis: MirrorAxis::default(),
+            snap_angle: SNAP_ANGLE,
+            ripple_preview: false,
+            curve_style: CurveStyle::default(),
+            show_guide: true,
+            breathing: false,
+            breath_amplitude: BREATH_AMPLITUDE,
+            breath_frequency: BREATH_FREQUENCY,
+            constrain_to_bounds: false,
+        }
+    }
+}
+
+/// A second, read-only curve drawn over the active one for before/after
+/// comparisons.
+struct Comparison {
+    points: Vec<Pt>,
+    closed: bool,
+    iters: Vec<Vec<Pt>>,
+    visible: bool,
+}
+
+/// A curve that is not being edited: one stored beside the active curve,
+/// or the one removed by the last clear, kept so the clear can be undone.
+struct Curve {
+    points: Vec<Pt>,
+    labels: Vec<Option<String>>,
+    weights: Vec<f32>,
+    mirror_pairs: Vec<Option<MirrorPair>>,
+    closed: bool,
+}
+
+impl Curve {
+    fn is_closed(&self) -> bool {
+        self.closed || ends_meet(&self.points)
+    }
+
+    /// The points without the repeated closing point, if any.
+    fn control_polygon(&self) -> &[Pt] {
+        let n = self.points.len();
+        if self.is_closed() && n >= 2 {
+            &self.points[..n - 1]
+        } else {
+            &self.points
+        }
+    }
+}
+
+/// What a finished text entry is applied to.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum EntryTarget {
+    /// Label for the control point at this index.
+    Label(usize),
+    /// A new control point typed as `x y`.
+    Point,
+}
+
+/// An in-progress line of typed text, captured instead of shortcuts.
+struct TextEntry {
+    target: EntryTarget,
+    text: String,
+    /// The character of the key that opened the entry, which arrives as a
+    /// typed character right after the key press and must be dropped.
+    swallow: Option<char>,
+}
+
 pub struct App {
     control_points: Vec<Pt>,
+    /// Optional label per control point, kept parallel to `control_points`.
+    labels: Vec<Option<String>>,
+    /// Rational weight per control point (see `precompute_iterations`),
+    /// kept parallel to `control_points`.
+    weights: Vec<f32>,
+    /// Live-mirror pairing per control point, kept parallel to
+    /// `control_points`. Points added any other way have none.
+    mirror_pairs: Vec<Option<MirrorPair>>,
+    next_pair_id: u32,
+    selected: Option<usize>,
+    text_entry: Option<TextEntry>,
+    last_cleared: Option<Curve>,
+    action_log: ActionLog,
     cached_iters: Vec<Vec<Pt>>,
     dragging: Option<usize>,
     last_mouse_pos: Vector2<f32>,
+    width: f32,
+    height: f32,
+    max_steps: usize,
+    sheet_columns: usize,
+    /// Display time per level, always `max_steps + 1` long.
+    level_durations: Vec<Duration>,
     anim_running: bool,
+    /// Set by manual stepping: the animation stays visible but holds its
+    /// level until Enter resumes it.
+    anim_held: bool,
     anim_step: usize,
     last_anim_instant: Instant,
+    last_frame_instant: Instant,
+    /// Time origin for the breathing offsets.
+    breath_start: Instant,
+    /// Points last moved by `constrain_to_bounds`, flashed until
+    /// `BOUNDS_FLASH` after `bounds_flash_start`.
+    bounds_flash: Vec<usize>,
+    bounds_flash_start: Instant,
     closed: bool,
+    settings: Settings,
+    /// The settings the app started with, after applying the config.
+    default_settings: Settings,
+    comparison: Option<Comparison>,
+    /// Reference polyline drawn for tracing; never edited or subdivided.
+    guide: Option<Guide>,
+    /// Curves other than the one being edited, switched to with Tab.
+    other_curves: Vec<Curve>,
+    /// Cached levels for each of `other_curves`, kept parallel to it.
+    other_iters: Vec<Vec<Vec<Pt>>>,
+    modifiers: ModifiersState,
+    font: Font,
+    /// Crossing control-polygon edge pairs, when detection is enabled.
+    intersections: Vec<(usize, usize)>,
+}
+
+impl Default for App {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl App {
     pub fn new() -> Self {
+        Self::with_config(&Config::default())
+    }
+
+    pub fn with_config(config: &Config) -> Self {
         let control_points = Vec::new();
+        let settings = Settings {
+            point_budget: config.point_budget,
+            min_edge_len: config.min_edge_len,
+            smooth_mode: config.smooth_mode,
+            pinned_level: config.pin_level.map(|level| level.min(config.max_steps)),
+            snap_angle: config.snap_angle,
+            breath_amplitude: config.breath_amplitude,
+            breath_frequency: config.breath_frequency,
+            export: ExportOptions {
+                scale: config.export_scale,
+                ..ExportOptions::default()
+            },
+            ..Settings::default()
+        };
         Self {
-            cached_iters: precompute_iterations(&control_points, MAX_STEPS, false),
+            cached_iters: precompute_iterations(&control_points, &[], config.max_steps, false, &Subdivision::default()),
             control_points,
+            labels: Vec::new(),
+            weights: Vec::new(),
+            mirror_pairs: Vec::new(),
+            next_pair_id: 0,
+            selected: None,
+            text_entry: None,
+            last_cleared: None,
+            action_log: ActionLog::new(ACTION_LOG_CAPACITY),
             dragging: None,
             last_mouse_pos: Vector2::new(0.0, 0.0),
+            width: config.width,
+            height: config.height,
+            max_steps: config.max_steps,
+            sheet_columns: config.sheet_columns,
+            level_durations: (0..=config.max_steps)
+                .map(|level| config.level_durations.get(level).copied().unwrap_or(config.anim_interval))
+                .collect(),
             anim_running: false,
+            anim_held: false,
             anim_step: 0,
             last_anim_instant: Instant::now(),
+            last_frame_instant: Instant::now(),
+            breath_start: Instant::now(),
+            bounds_flash: Vec::new(),
+            bounds_flash_start: Instant::now(),
             closed: false,
+            default_settings: settings.clone(),
+            settings,
+            comparison: None,
+            guide: None,
+            other_curves: Vec::new(),
+            other_iters: Vec::new(),
+            modifiers: ModifiersState::default(),
+            font: Font::new(HUD_FONT).expect("bundled HUD font is valid"),
+            intersections: Vec::new(),
         }
     }
 
-    fn mouse_pos_to_pt(pos: Vector2<f32>) -> Pt {
+    /// Returns `n` points spaced evenly by arc length along the deepest
+    /// smoothed level, so the count no longer depends on the number of
+    /// control points or subdivision steps. Open curves include both ends;
+    /// closed curves start at the first point and stop one spacing short of
+    /// it, so the samples wrap around evenly. Empty without control points.
+    pub fn curve_samples(&self, n: usize) -> Vec<Pt> {
+        let poly = match self.cached_iters.last() {
+            Some(poly) if !poly.is_empty() => poly,
+            _ => return Vec::new(),
+        };
+        let closed = self.is_closed();
+        let spacing = if closed { n } else { n.saturating_sub(1).max(1) };
+        (0..n).map(|i| sample_at(poly, i as f32 / spacing as f32, closed)).collect()
+    }
+
+    fn mouse_pos_to_pt(&self, pos: Vector2<f32>) -> Pt {
         Pt {
-            x: pos.x.clamp(0.0, WIDTH),
-            y: pos.y.clamp(0.0, HEIGHT),
+            x: pos.x.clamp(0.0, self.width),
+            y: pos.y.clamp(0.0, self.height),
         }
     }
 
+    /// The polyline currently on screen. Every level is cached even for
+    /// tiny inputs: with no points it is empty, one point stays one point,
+    /// and two points subdivide into the same straight segment.
+    fn displayed_level(&self) -> &[Pt] {
+        &self.cached_iters[self.anim_step]
+    }
+
+    /// The finished curve: the pinned level if one is set, else the deepest.
+    /// Unlike `displayed_level` it does not follow the animation.
+    fn finished_level(&self) -> &[Pt] {
+        let pinned = self.settings.pinned_level.and_then(|level| self.cached_iters.get(level));
+        pinned.or(self.cached_iters.last()).map_or(&[], Vec::as_slice)
+    }
+
+    /// The point within `radius` closest to `pt`. Equally close points go to
+    /// the highest index, which is drawn on top. The repeated closing point
+    /// of a closed curve is never picked, so grabbing the start gives point 0.
     fn find_point_index_near(&self, pt: Pt, radius: f32) -> Option<usize> {
         let r2 = radius * radius;
-        self.control_points
+        self.control_polygon()
             .iter()
-            .position(|p| dist2(*p, pt) <= r2)
+            .enumerate()
+            .map(|(i, p)| (i, dist2(*p, pt)))
+            .filter(|&(_, d)| d <= r2)
+            .min_by(|a, b| a.1.total_cmp(&b.1).then(b.0.cmp(&a.0)))
+            .map(|(i, _)| i)
     }
 
-    fn recompute_cache(&mut self) {
-        self.cached_iters = precompute_iterations(&self.control_points, MAX_STEPS, false);
-        if self.anim_step >= self.cached_iters.len() {
-            self.anim_step = 0;
+    /// Appends a control point, keeping the per-point data in step.
+    fn push_point(&mut self, p: Pt) {
+        self.control_points.push(p);
+        self.labels.push(None);
+        self.weights.push(1.0);
+        self.mirror_pairs.push(None);
+    }
+
+    fn insert_point(&mut self, i: usize, p: Pt) {
+        self.control_points.insert(i, p);
+        self.labels.insert(i, None);
+        self.weights.insert(i, 1.0);
+        self.mirror_pairs.insert(i, None);
+    }
+
+    fn mirrored(&self, p: Pt) -> Pt {
+        match self.settings.mirror_axis {
+            MirrorAxis::Vertical => Pt { x: self.width - p.x, y: p.y },
+            MirrorAxis::Horizontal => Pt { x: p.x, y: self.height - p.y },
         }
     }
 
-    fn draw_line(&self, graphics: &mut Graphics2D, a: Pt, b: Pt, thickness: f32, highlight: bool) {
-    if self.anim_running {
-        let color = if highlight {
-            Color::GREEN
-        } else {
-            Color::from_rgb(0.07, 0.07, 0.07)
-        };
+    /// The point halfway between `p` and its mirror image, i.e. `p` moved
+    /// onto the axis.
+    fn onto_axis(&self, p: Pt) -> Pt {
+        midpoint(p, self.mirrored(p))
+    }
 
-        let a: Vector2<f32> = a.into();
-        let b: Vector2<f32> = b.into();
-        graphics.draw_line(a, b, thickness, color);
+    /// Adds `p` and its mirror image in live-mirror mode, recording the
+    /// pair in `mirror_pairs`.
+    ///
+    /// New pairs go into the middle of the list, so a curve drawn this way
+    /// runs out along one side and back along the other. A click within
+    /// `CLICK_RADIUS` of the axis adds a single point on it, as long as the
+    /// list has an even length.
+    fn add_mirrored(&mut self, p: Pt) {
+        let n = self.control_points.len();
+        let half = n / 2;
+        let on_axis = dist2(p, self.onto_axis(p)) <= CLICK_RADIUS * CLICK_RADIUS;
+        if on_axis && n.is_multiple_of(2) {
+            let p = self.onto_axis(p);
+            self.insert_point(half, p);
+            self.mirror_pairs[half] = Some(MirrorPair::OnAxis);
+            self.action_log.record(Action::Added(p));
+        } else {
+            let m = self.mirrored(p);
+            let pair = Some(MirrorPair::Pair(self.next_pair_id));
+            self.next_pair_id += 1;
+            self.insert_point(half, p);
+            self.insert_point(n + 1 - half, m);
+            self.mirror_pairs[half] = pair;
+            self.mirror_pairs[n + 1 - half] = pair;
+            self.action_log.record(Action::Added(p));
+            self.action_log.record(Action::Added(m));
+        }
     }
-}
 
-}
+    /// The other point holding point `i`'s pair id, if it is still in the
+    /// active curve.
+    fn mirror_partner(&self, i: usize) -> Option<usize> {
+        let pair = self.mirror_pairs[i].filter(|pair| matches!(pair, MirrorPair::Pair(_)))?;
+        (0..self.mirror_pairs.len()).find(|&j| j != i && self.mirror_pairs[j] == Some(pair))
+    }
 
-impl WindowHandler for App {
-    fn on_draw(&mut self, helper: &mut WindowHelper, graphics: &mut Graphics2D) {
-        if self.anim_running && self.control_points.len() >= 3 && self.last_anim_instant.elapsed() >= ANIM_INTERVAL {
-            self.last_anim_instant = Instant::now();
-            self.anim_step = (self.anim_step + 1) % (MAX_STEPS + 1);
-        } else if !self.anim_running {
+    /// Snaps `p` onto the finished curve and inserts it into the control
+    /// polygon at the span that produced the nearest curve segment, so the
+    /// new point refines the shape where it was clicked instead of
+    /// extending it. The producing control points come from `ripple_mask`;
+    /// among their spans, and among all spans when the scheme cannot say,
+    /// the one whose edge is closest to the snapped point wins. Returns
+    /// `false`, adding nothing, without a curve or when the spot is taken.
+    fn insert_on_curve(&mut self, p: Pt) -> bool {
+        let m = self.control_polygon().len();
+        if m < 2 {
+            return false;
+        }
+        let level = self.settings.pinned_level.map_or(self.max_steps, |l| l.min(self.max_steps));
+        let poly = &self.cached_iters[level];
+        let closed = self.is_closed();
+        let Some((seg, s)) = edges(poly, closed)
+            .map(|(a, b)| closest_point_on_segment(p, a, b))
+            .enumerate()
+            .min_by(|a, b| dist2(a.1, p).total_cmp(&dist2(b.1, p)))
+        else {
+            return false;
+        };
+        if self.control_points.iter().any(|q| dist2(*q, s) < 250.0) {
+            return false;
+        }
+
+        let ends = [seg, (seg + 1) % poly.len()];
+        let producers: Vec<bool> = (0..m)
+            .map(|i| self.ripple_mask(i, level).is_some_and(|mask| ends.iter().any(|&j| mask[j])))
+            .collect();
+        let spans = if closed { m } else { m - 1 };
+        let span_ends = |k: usize| (k, (k + 1) % m);
+        let both: Vec<usize> = (0..spans).filter(|&k| producers[k] && producers[span_ends(k).1]).collect();
+        let either: Vec<usize> = (0..spans).filter(|&k| producers[k] || producers[span_ends(k).1]).collect();
+        let candidates = if !both.is_empty() {
+            both
+        } else if !either.is_empty() {
+            either
+        } else {
+            (0..spans).collect()
+        };
+        let gap = |k: usize| {
+            let (a, b) = span_ends(k);
+            let (a, b) = (self.control_points[a], self.control_points[b]);
+            dist2(closest_point_on_segment(s, a, b), s)
+        };
+        let Some(k) = candidates.into_iter().min_by(|&a, &b| gap(a).total_cmp(&gap(b))) else {
+            return false;
+        };
+
+        // a closed curve's closing span goes in before the repeated point
+        self.insert_point(k + 1, s);
+        self.action_log.record(Action::Added(s));
+        true
+    }
+
+    /// The point a Shift-drag of point `i` is constrained against: the
+    /// previous point, or the next one for the first point.
+    fn snap_anchor(&self, i: usize) -> Option<Pt> {
+        let j = i.checked_sub(1).or((i + 1 < self.control_points.len()).then_some(i + 1))?;
+        Some(self.control_points[j])
+    }
+
+    /// Moves point `i`, taking its partner along in live-mirror mode.
+    /// Points without a pairing move alone. Moving the start of a closed
+    /// curve moves its repeated closing point too.
+    fn move_point(&mut self, i: usize, p: Pt) {
+        if i == 0 && self.is_closed() {
+            let last = self.control_points.len() - 1;
+            self.control_points[last] = p;
+        }
+        if !self.settings.mirror || self.closed {
+            self.control_points[i] = p;
+            return;
+        }
+        if self.mirror_pairs[i] == Some(MirrorPair::OnAxis) {
+            self.control_points[i] = self.onto_axis(p);
+            return;
+        }
+        self.control_points[i] = p;
+        if let Some(j) = self.mirror_partner(i) {
+            self.control_points[j] = self.mirrored(p);
+        }
+    }
+
+    /// Removes every point, keeping them aside so `undo_clear` can bring
+    /// them back.
+    fn clear(&mut self) {
+        self.action_log.record(Action::Cleared);
+        if !self.control_points.is_empty() {
+            self.last_cleared = Some(Curve {
+                points: std::mem::take(&mut self.control_points),
+                labels: std::mem::take(&mut self.labels),
+                weights: std::mem::take(&mut self.weights),
+                mirror_pairs: std::mem::take(&mut self.mirror_pairs),
+                closed: self.closed,
+            });
+        }
+        self.selected = None;
+        self.dragging = None;
+        self.recompute_cache();
+        self.anim_running = false;
+        self.anim_held = false;
+        self.closed = false;
+        self.anim_step = 0;
+    }
+
+    /// Restores the points removed by the last clear, if nothing has been
+    /// drawn since.
+    fn undo_clear(&mut self) {
+        if !self.control_points.is_empty() {
+            return;
+        }
+        if let Some(cleared) = self.last_cleared.take() {
+            self.control_points = cleared.points;
+            self.labels = cleared.labels;
+            self.weights = cleared.weights;
+            self.mirror_pairs = cleared.mirror_pairs;
+            self.closed = cleared.closed;
+            self.recompute_cache();
+            self.action_log.record(Action::Restored);
+        }
+    }
+
+    /// Turns an open curve into a symmetric closed loop by appending the
+    /// interior points mirrored across the line through the first and last
+    /// points, in reverse order.
+    fn reflect_to_closed_loop(&mut self) {
+        let n = self.control_points.len();
+        if n < 3 || self.closed {
+            return;
+        }
+
+        // with coincident endpoints there is no axis, and the curve already
+        // reads as closed
+        let (first, last) = (self.control_points[0], self.control_points[n - 1]);
+        if dist2(first, last) <= CLICK_RADIUS * CLICK_RADIUS {
+            println!("cannot reflect: first and last points coincide");
+            return;
+        }
+
+        for i in (1..n - 1).rev() {
+            let p = reflect_across(self.control_points[i], first, last);
+            self.push_point(p);
+        }
+        self.push_point(first);
+        self.closed = true;
+        self.recompute_cache();
+        self.action_log.record(Action::Reflected);
+    }
+
+    /// Inserts a coincident copy right after the selected point, or the one
+    /// under the mouse. Chaikin cuts a zero-length edge into the point
+    /// itself, so a doubled point makes the curve pass through it with a
+    /// sharp corner, the usual way to add corners to a smooth closed curve.
+    /// The cubic scheme is pulled towards the point but stays rounded.
+    fn duplicate_point(&mut self) {
+        let hovered = || self.find_point_index_near(self.mouse_pos_to_pt(self.last_mouse_pos), CLICK_RADIUS);
+        let Some(i) = self.selected.filter(|&i| i < self.control_points.len()).or_else(hovered) else {
+            return;
+        };
+        // inserted directly, bypassing the click guard against stacked points
+        self.control_points.insert(i + 1, self.control_points[i]);
+        self.labels.insert(i + 1, None);
+        self.weights.insert(i + 1, self.weights[i]);
+        self.mirror_pairs.insert(i + 1, None);
+        self.recompute_cache();
+        self.action_log.record(Action::Duplicated(i));
+    }
+
+    fn is_closed(&self) -> bool {
+        ends_meet(&self.control_points)
+    }
+
+    /// Writes the pinned level, or else the deepest one, to `chaikin.<ext>`
+    /// in the working directory.
+    fn export(&self, format: ExportFormat) {
+        let poly = self.finished_level();
+        if poly.len() < 2 {
+            println!("nothing to export: add at least two points");
+            return;
+        }
+
+        let job = ExportJob {
+            poly,
+            control: self.control_polygon(),
+            closed: self.is_closed(),
+            width: self.width,
+            height: self.height,
+            options: self.settings.export,
+        };
+        let path = PathBuf::from(format!("chaikin.{}", format.extension()));
+        match job.write(format, &path) {
+            Ok(()) => println!("exported {}", path.display()),
+            Err(e) => eprintln!("failed to export {}: {e}", path.display()),
+        }
+    }
+
+    /// Writes every level, level 0 first, as the tiles of one PNG with
+    /// `sheet_columns` tiles per row.
+    fn export_sprite_sheet(&self, path: &Path) {
+        if self.control_points.len() < 2 {
+            println!("nothing to export: add at least two points");
+            return;
+        }
+        let sheet = SpriteSheet {
+            levels: &self.cached_iters,
+            closed: self.is_closed(),
+            width: self.width,
+            height: self.height,
+            columns: self.sheet_columns,
+            options: self.settings.export,
+        };
+        match sheet.write(path) {
+            Ok((w, h)) => println!(
+                "exported {} ({w}x{h}, {} levels, {} per row)",
+                path.display(),
+                self.cached_iters.len(),
+                self.sheet_columns.min(self.cached_iters.len())
+            ),
+            Err(e) => eprintln!("failed to export {}: {e}", path.display()),
+        }
+    }
+
+    fn dump_action_log(&self, path: &Path) {
+        match self.action_log.write(path) {
+            Ok(()) => println!("wrote action log to {}", path.display()),
+            Err(e) => eprintln!("failed to write {}: {e}", path.display()),
+        }
+    }
+
+    fn save_curve(&self, path: &Path) {
+        let points = self.control_polygon().to_vec();
+        let curve = CurveFile {
+            labels: self.labels[..points.len()].to_vec(),
+            points,
+            closed: self.is_closed(),
+        };
+        match curve_file::save(path, &curve) {
+            Ok(()) => println!("saved {}", path.display()),
+            Err(e) => eprintln!("failed to save {}: {e}", path.display()),
+        }
+    }
+
+    fn open_curve(&mut self, path: &Path) {
+        match curve_file::load(path) {
+            Ok(curve) => {
+                self.control_points = curve.points;
+                self.labels = curve.labels;
+                self.weights = vec![1.0; self.control_points.len()];
+                self.mirror_pairs = vec![None; self.control_points.len()];
+                // closed curves are edited with the first point repeated last
+                self.closed = curve.closed && self.control_points.len() >= 3;
+                if self.closed {
+                    self.push_point(self.control_points[0]);
+                }
+                self.dragging = None;
+                self.selected = None;
+                self.text_entry = None;
+                self.recompute_cache();
+                self.action_log.record(Action::Opened(path.display().to_string()));
+                println!("opened {}", path.display());
+            }
+            Err(e) => eprintln!("failed to open {}: {e}", path.display()),
+        }
+    }
+
+    /// Loads the first of `GUIDE_FILES` that exists.
+    fn load_guide(&mut self) {
+        let Some(path) = GUIDE_FILES.iter().map(Path::new).find(|p| p.is_file()) else {
+            println!("no guide found: add {} or {}", GUIDE_FILES[0], GUIDE_FILES[1]);
+            return;
+        };
+        match guide::load(path) {
+            Ok(guide) => {
+                self.guide = Some(guide);
+                self.settings.show_guide = true;
+                println!("loaded guide {}", path.display());
+            }
+            Err(e) => eprintln!("failed to load {}: {e}", path.display()),
+        }
+    }
+
+    fn load_comparison(&mut self, path: &Path) {
+        match curve_file::load(path) {
+            Ok(curve) => {
+                self.comparison = Some(Comparison {
+                    points: curve.points,
+                    closed: curve.closed,
+                    iters: Vec::new(),
+                    visible: true,
+                });
+                self.recompute_comparison();
+                println!("comparing against {}", path.display());
+            }
+            Err(e) => eprintln!("failed to load comparison {}: {e}", path.display()),
+        }
+    }
+
+    fn subdivision(&self) -> Subdivision {
+        Subdivision {
+            mode: self.settings.smooth_mode,
+            min_edge: self.settings.skip_short_edges.then_some(self.settings.min_edge_len),
+            end_handles: self.settings.end_handles,
+        }
+    }
+
+    /// A copy of the control polygon (without the closing duplicate), each
+    /// point moved along its own slow Lissajous loop; the points themselves
+    /// are never changed.
+    fn breathing_points(&self) -> Vec<Pt> {
+        let t = self.breath_start.elapsed().as_secs_f32() * self.settings.breath_frequency * std::f32::consts::TAU;
+        let amp = self.settings.breath_amplitude;
+        self.control_polygon()
+            .iter()
+            .enumerate()
+            .map(|(i, p)| {
+                // spread the phases so neighbours do not move in lockstep
+                let phase = i as f32 * 2.399_963;
+                Pt {
+                    x: p.x + amp * (t + phase).sin(),
+                    y: p.y + amp * (t * 0.8 + phase * 1.7).cos(),
+                }
+            })
+            .collect()
+    }
+
+    /// Clamps every control point into the canvas if the setting is on.
+    /// Runs before each recompute, so it covers every way a point can get
+    /// out: transforms, typed coordinates, files written at another size.
+    fn constrain_to_bounds(&mut self) {
+        if !self.settings.constrain_to_bounds {
+            return;
+        }
+        let (w, h) = (self.width, self.height);
+        let moved: Vec<usize> = self
+            .control_points
+            .iter_mut()
+            .enumerate()
+            .filter_map(|(i, p)| {
+                let inside = Pt {
+                    x: p.x.clamp(0.0, w),
+                    y: p.y.clamp(0.0, h),
+                };
+                (inside.x != p.x || inside.y != p.y).then(|| {
+                    *p = inside;
+                    i
+                })
+            })
+            .collect();
+        if !moved.is_empty() {
+            println!("moved {} point(s) back inside the canvas", moved.len());
+            self.bounds_flash = moved;
+            self.bounds_flash_start = Instant::now();
+        }
+    }
+
+    fn recompute_cache(&mut self) {
+        self.constrain_to_bounds();
+        // closure is decided once, on the real points
+        let closed = self.is_closed();
+        let base = if self.settings.breathing {
+            Cow::Owned(self.breathing_points())
+        } else {
+            Cow::Borrowed(self.control_polygon())
+        };
+        self.cached_iters = precompute_iterations(&base, &self.weights, self.max_steps, closed, &self.subdivision());
+        if self.anim_step >= self.cached_iters.len() {
             self.anim_step = 0;
         }
+        self.update_intersections();
+    }
 
-        let to_draw = if self.control_points.len() >= 3 {
-            &self.cached_iters[self.anim_step]
+    /// The control polygon without the repeated closing point, if any.
+    fn control_polygon(&self) -> &[Pt] {
+        let n = self.control_points.len();
+        if self.is_closed() {
+            &self.control_points[..n - 1]
         } else {
             &self.control_points
+        }
+    }
+
+    fn update_intersections(&mut self) {
+        self.intersections = if self.settings.detect_intersections {
+            self_intersections(self.control_polygon(), self.is_closed())
+        } else {
+            Vec::new()
+        };
+    }
+
+    /// Applies a change to a setting that affects subdivision. Every such
+    /// change goes through here so the cached levels never go stale.
+    fn set_mode(&mut self, change: impl FnOnce(&mut Settings)) {
+        change(&mut self.settings);
+        self.recompute_cache();
+        self.recompute_comparison();
+        self.recompute_other_curves();
+    }
+
+    /// Puts every runtime setting back to its startup value without touching
+    /// the points.
+    fn reset_settings(&mut self) {
+        let defaults = self.default_settings.clone();
+        self.set_mode(|s| *s = defaults);
+    }
+
+    /// The comparison curve never changes, so it is only re-subdivided when
+    /// the subdivision settings do.
+    fn recompute_comparison(&mut self) {
+        let (max_steps, scheme) = (self.max_steps, self.subdivision());
+        if let Some(cmp) = &mut self.comparison {
+            cmp.iters = precompute_iterations(&cmp.points, &[], max_steps, cmp.closed, &scheme);
+        }
+    }
+
+    fn recompute_other_curves(&mut self) {
+        let (max_steps, scheme) = (self.max_steps, self.subdivision());
+        self.other_iters = self
+            .other_curves
+            .iter()
+            .map(|c| precompute_iterations(c.control_polygon(), &c.weights, max_steps, c.is_closed(), &scheme))
+            .collect();
+    }
+
+    /// Takes the active curve out of the editor, leaving it empty.
+    fn take_active(&mut self) -> Curve {
+        let curve = Curve {
+            points: std::mem::take(&mut self.control_points),
+            labels: std::mem::take(&mut self.labels),
+            weights: std::mem::take(&mut self.weights),
+            mirror_pairs: std::mem::take(&mut self.mirror_pairs),
+            closed: std::mem::take(&mut self.closed),
+        };
+        self.selected = None;
+        self.dragging = None;
+        self.recompute_cache();
+        curve
+    }
+
+    fn set_active(&mut self, curve: Curve) {
+        self.control_points = curve.points;
+        self.labels = curve.labels;
+        self.weights = curve.weights;
+        self.mirror_pairs = curve.mirror_pairs;
+        self.closed = curve.closed;
+        self.selected = None;
+        self.dragging = None;
+        self.recompute_cache();
+    }
+
+    /// Keeps `curve` beside the active one; empty curves are dropped.
+    fn store_curve(&mut self, curve: Curve) {
+        if !curve.points.is_empty() {
+            self.other_curves.push(curve);
+            self.recompute_other_curves();
+        }
+    }
+
+    /// Makes the oldest stored curve active and stores the current one
+    /// behind the others.
+    fn cycle_curves(&mut self) {
+        if self.other_curves.is_empty() {
+            return;
+        }
+        let next = self.other_curves.remove(0);
+        self.other_iters.remove(0);
+        let current = self.take_active();
+        self.store_curve(current);
+        self.set_active(next);
+    }
+
+    /// Splits the active open curve at the selected point: the active curve
+    /// keeps the points up to it, and the rest, starting again at the same
+    /// point, is stored as a new curve. A closed curve is instead opened at
+    /// the selected point, becoming one open curve that starts there and
+    /// runs once around; its edge back into that point is dropped, since
+    /// coincident endpoints would read as closed again.
+    fn split_at_selected(&mut self) {
+        let Some(i) = self.selected.filter(|&i| i < self.control_points.len()) else {
+            return;
+        };
+
+        if self.is_closed() {
+            let n = self.control_polygon().len();
+            let i = i % n;
+            self.control_points.truncate(n);
+            self.labels.truncate(n);
+            self.weights.truncate(n);
+            self.mirror_pairs.truncate(n);
+            self.control_points.rotate_left(i);
+            self.labels.rotate_left(i);
+            self.weights.rotate_left(i);
+            self.mirror_pairs.rotate_left(i);
+            self.closed = false;
+            self.selected = Some(0);
+            self.recompute_cache();
+            self.action_log.record(Action::Split(i));
+            return;
+        }
+
+        if i == 0 || i + 1 >= self.control_points.len() {
+            println!("cannot split at an end point");
+            return;
+        }
+        let mut tail = Curve {
+            points: self.control_points[i..].to_vec(),
+            labels: self.labels[i..].to_vec(),
+            weights: self.weights[i..].to_vec(),
+            mirror_pairs: self.mirror_pairs[i..].to_vec(),
+            closed: false,
+        };
+        // the shared point keeps its label on the active half only
+        tail.labels[0] = None;
+        self.control_points.truncate(i + 1);
+        self.labels.truncate(i + 1);
+        self.weights.truncate(i + 1);
+        self.mirror_pairs.truncate(i + 1);
+        self.store_curve(tail);
+        self.recompute_cache();
+        self.action_log.record(Action::Split(i));
+    }
+
+    /// Appends the stored open curve whose endpoint is nearest to one of
+    /// the active open curve's endpoints. Either curve is reversed as
+    /// needed so that the active curve's end meets the other's start, and a
+    /// shared point (within `CLICK_RADIUS`) is kept once, so joining the
+    /// halves of a split gives back the original curve.
+    fn join_nearest(&mut self) {
+        if self.control_points.is_empty() || self.is_closed() {
+            return;
+        }
+        let (start, end) = (self.control_points[0], *self.control_points.last().unwrap());
+
+        // (curve index, reverse active, reverse other, squared gap)
+        let mut best: Option<(usize, bool, bool, f32)> = None;
+        for (k, curve) in self.other_curves.iter().enumerate() {
+            if curve.points.is_empty() || curve.is_closed() {
+                continue;
+            }
+            let (other_start, other_end) = (curve.points[0], *curve.points.last().unwrap());
+            let options = [
+                (false, false, dist2(end, other_start)),
+                (false, true, dist2(end, other_end)),
+                (true, false, dist2(start, other_start)),
+                (true, true, dist2(start, other_end)),
+            ];
+            for (rev_active, rev_other, d) in options {
+                if best.is_none_or(|b| d < b.3) {
+                    best = Some((k, rev_active, rev_other, d));
+                }
+            }
+        }
+        let Some((k, rev_active, rev_other, gap)) = best else {
+            return;
         };
 
+        let mut other = self.other_curves.remove(k);
+        self.other_iters.remove(k);
+        if rev_active {
+            self.control_points.reverse();
+            self.labels.reverse();
+            self.weights.reverse();
+            self.mirror_pairs.reverse();
+        }
+        if rev_other {
+            other.points.reverse();
+            other.labels.reverse();
+            other.weights.reverse();
+            other.mirror_pairs.reverse();
+        }
+        if gap <= CLICK_RADIUS * CLICK_RADIUS {
+            let shared = other.labels.remove(0);
+            other.points.remove(0);
+            other.weights.remove(0);
+            other.mirror_pairs.remove(0);
+            let last = self.labels.last_mut().unwrap();
+            if last.is_none() {
+                *last = shared;
+            }
+        }
+        self.control_points.extend(other.points);
+        self.labels.extend(other.labels);
+        self.weights.extend(other.weights);
+        self.mirror_pairs.extend(other.mirror_pairs);
+        self.selected = None;
+        self.recompute_cache();
+        self.action_log.record(Action::Joined);
+    }
+
+    /// Draws the stored curves at the displayed level, dimmed, with small
+    /// markers that stay visible while paused.
+    fn draw_other_curves(&self, graphics: &mut Graphics2D) {
+        let color = Color::from_rgb(0.3, 0.5, 0.3);
+        for (curve, iters) in self.other_curves.iter().zip(&self.other_iters) {
+            let Some(poly) = iters.get(self.anim_step).or(iters.last()) else {
+                continue;
+            };
+            for (a, b) in edges(poly, curve.is_closed()) {
+                self.draw_segment(graphics, a, b, 1.0, color);
+            }
+            for p in &curve.points {
+                graphics.draw_circle(Vector2::<f32>::from(*p), 2.5, Color::from_rgb(0.45, 0.45, 0.45));
+            }
+        }
+    }
+
+    fn draw_line(&self, graphics: &mut Graphics2D, a: Pt, b: Pt, thickness: f32, highlight: bool) {
+        let color = if highlight {
+            Color::GREEN
+        } else {
+            Color::from_rgb(0.07, 0.07, 0.07)
+        };
+        self.draw_segment(graphics, a, b, thickness, color);
+    }
+
+    /// `thickness` is in screen pixels. Points are stored in window pixels
+    /// and drawn without a view transform, so this is also world units.
+    fn draw_segment(&self, graphics: &mut Graphics2D, a: Pt, b: Pt, thickness: f32, color: Color) {
+        if self.anim_running {
+            let a: Vector2<f32> = a.into();
+            let b: Vector2<f32> = b.into();
+            graphics.draw_line(a, b, thickness, color);
+        }
+    }
+
+    /// Fills the inside of a closed curve. Like the outline, it is only shown
+    /// while the animation runs.
+    fn draw_fill(&self, graphics: &mut Graphics2D, poly: &[Pt]) {
+        if !self.anim_running || poly.len() < 3 {
+            return;
+        }
+        let polygon = Polygon::new(poly);
+        graphics.draw_polygon(&polygon, (0.0, 0.0), Color::from_rgba(0.0, 1.0, 0.0, 0.35));
+    }
+
+    /// Rounds off the corners between the segments of `poly` when round
+    /// joins are enabled; the open ends keep their square caps. With
+    /// per-edge `colors`, each joint takes the colour of the edge ending there.
+    fn draw_joins(&self, graphics: &mut Graphics2D, poly: &[Pt], closed: bool, thickness: f32, colors: Option<&[Color]>) {
+        if self.settings.line_join != LineJoin::Round || !self.anim_running || poly.len() < 3 {
+            return;
+        }
+        let n = poly.len();
+        let joints = if closed { 0..n } else { 1..n - 1 };
+        for i in joints {
+            let color = colors.map_or(Color::GREEN, |c| c[(i + c.len() - 1) % c.len()]);
+            graphics.draw_circle(Vector2::<f32>::from(poly[i]), thickness / 2.0, color);
+        }
+    }
+
+    /// Draws every cached level at once, from faint (level 0) to opaque
+    /// (deepest), independently of the animation.
+    fn draw_onion_skin(&self, graphics: &mut Graphics2D, closed: bool) {
+        let levels = self.cached_iters.len();
+        for (level, poly) in self.cached_iters.iter().enumerate() {
+            let t = (level + 1) as f32 / levels as f32;
+            let color = Color::from_rgba(0.2 + 0.8 * t, 1.0, 0.2 + 0.8 * (1.0 - t), 0.15 + 0.85 * t);
+            let poly = decimate(poly, self.settings.point_budget);
+            for w in poly.windows(2) {
+                graphics.draw_line(Vector2::from(w[0]), Vector2::from(w[1]), 1.0, color);
+            }
+            if closed && poly.len() >= 3 {
+                graphics.draw_line(Vector2::from(*poly.last().unwrap()), Vector2::from(poly[0]), 1.0, color);
+            }
+        }
+    }
+
+    fn draw_hud(&self, graphics: &mut Graphics2D, level: &[Pt], closed: bool, decimated: bool) {
+        if !self.settings.show_hud {
+            return;
+        }
+
+        let mut text = format!(
+            "{}  level {}/{}  points {}",
+            self.settings.smooth_mode.name(),
+            self.anim_step,
+            self.max_steps,
+            level.len()
+        );
+        if !self.other_curves.is_empty() {
+            text.push_str(&format!("  curves {}", self.other_curves.len() + 1));
+        }
+        if self.settings.pinned_level.is_some() {
+            text.push_str(" (pinned)");
+        } else if self.anim_held {
+            text.push_str(" (held)");
+        }
+        if decimated {
+            text.push_str(" (decimated)");
+        }
+        if let FramePacing::Fps(fps) = self.settings.frame_pacing {
+            text.push_str(&format!("  cap {fps} fps"));
+        }
+
+        let mut lines = vec![(text, Color::from_rgb(0.8, 0.8, 0.8))];
+
+        let segments = segment_count(level, closed);
+        if segments > 0 {
+            let avg = polyline_length(level, closed) / segments as f32;
+            lines.push((
+                format!("segments {segments}  avg length {avg:.2}"),
+                Color::from_rgb(0.8, 0.8, 0.8),
+            ));
+        }
+        if let Some(smooth) = self.cached_iters.last().filter(|_| self.control_points.len() >= 2) {
+            // the deepest level, whatever the animation shows, so the figure
+            // tracks every edit
+            lines.push((
+                format!(
+                    "perimeter {:.1}  smoothed length {:.1}",
+                    polyline_length(self.control_polygon(), self.is_closed()),
+                    polyline_length(smooth, closed)
+                ),
+                Color::from_rgb(0.8, 0.8, 0.8),
+            ));
+        }
+        if !self.intersections.is_empty() {
+            let warning = format!("warning: control polygon crosses itself ({})", self.intersections.len());
+            lines.push((warning, Color::from_rgb(1.0, 0.3, 0.3)));
+        }
+
+        for (i, (text, color)) in lines.iter().enumerate() {
+            let block = self.font.layout_text(text, HUD_FONT_SIZE, TextOptions::new());
+            graphics.draw_text((HUD_MARGIN, HUD_MARGIN + i as f32 * HUD_LINE_HEIGHT), *color, &block);
+        }
+    }
+
+    /// Draws each control-polygon edge with an arrowhead at its midpoint,
+    /// pointing from point `i` to point `i + 1`.
+    fn draw_direction_arrows(&self, graphics: &mut Graphics2D) {
+        if !self.settings.show_arrows {
+            return;
+        }
+
+        let color = Color::from_rgb(0.55, 0.55, 0.75);
+        for (a, b) in edges(self.control_polygon(), self.is_closed()) {
+            let len = dist2(a, b).sqrt();
+            if len <= f32::EPSILON {
+                continue;
+            }
+            graphics.draw_line(Vector2::from(a), Vector2::from(b), 1.0, color);
+
+            let mid = Vector2::new((a.x + b.x) * 0.5, (a.y + b.y) * 0.5);
+            let back = Vector2::new((a.x - b.x) / len, (a.y - b.y) / len) * ARROW_LEN;
+            for angle in [ARROW_SPREAD, -ARROW_SPREAD] {
+                let (sin, cos) = angle.sin_cos();
+                let wing = Vector2::new(back.x * cos - back.y * sin, back.x * sin + back.y * cos);
+                graphics.draw_line(mid, mid + wing, 1.5, color);
+            }
+        }
+    }
+
+    /// Draws the midpoints of the control-polygon edges and the polygon
+    /// through them. The quadratic B-spline that Chaikin converges to touches
+    /// this polygon at each midpoint, tangent to the control edge there.
+    fn draw_tangent_polygon(&self, graphics: &mut Graphics2D) {
+        if !self.settings.show_tangent_polygon {
+            return;
+        }
+
+        let closed = self.is_closed();
+        let mids: Vec<Pt> = edges(self.control_polygon(), closed).map(|(a, b)| midpoint(a, b)).collect();
+        let color = Color::from_rgb(0.3, 0.8, 0.9);
+        for (a, b) in edges(&mids, closed) {
+            graphics.draw_line(Vector2::from(a), Vector2::from(b), 1.0, color);
+        }
+        for m in &mids {
+            graphics.draw_circle(Vector2::from(*m), 2.5, color);
+        }
+    }
+
+    /// The points of `level` that move with control point `i`, traced from
+    /// the subdivision's local support: flag the level-0 points made from
+    /// `i` and push the flags through `level` steps of the stencil.
+    fn ripple_mask(&self, i: usize, level: usize) -> Option<Vec<bool>> {
+        let n = self.control_points.len();
+        let base_len = self.cached_iters[0].len();
+        let mut mask = vec![false; base_len];
+        if self.is_closed() {
+            // the repeated closing point is point 0
+            mask[if i + 1 == n { 0 } else { i }] = true;
+        } else if self.settings.end_handles && n >= 4 {
+            // see `with_end_handles` for where each point ends up
+            match i {
+                0 => mask[1] = true,
+                1 => mask[..2].fill(true),
+                _ if i == n - 1 => mask[n - 2] = true,
+                _ if i == n - 2 => mask[n - 2..].fill(true),
+                _ => mask[i] = true,
+            }
+        } else {
+            mask[i] = true;
+        }
+
+        let scheme = self.subdivision();
+        let closed = self.is_closed();
+        for _ in 0..level {
+            mask = scheme.step_mask(&mask, closed)?;
+        }
+        (mask.len() == self.cached_iters[level].len()).then_some(mask)
+    }
+
+    /// Draws the edges of the displayed level that the dragged point moves,
+    /// showing Chaikin's local control: everything outside stays put.
+    fn draw_ripple(&self, graphics: &mut Graphics2D) {
+        let Some(i) = self.dragging.filter(|_| self.settings.ripple_preview) else {
+            return;
+        };
+        if i >= self.control_points.len() {
+            return;
+        }
+        let Some(mask) = self.ripple_mask(i, self.anim_step) else {
+            return;
+        };
+        let poly = &self.cached_iters[self.anim_step];
+        let n = poly.len();
+        let closed = self.is_closed() && n >= 3;
+        let color = Color::from_rgb(1.0, 0.3, 0.9);
+        for j in 0..segment_count(poly, closed) {
+            let k = (j + 1) % n;
+            if mask[j] || mask[k] {
+                graphics.draw_line(Vector2::from(poly[j]), Vector2::from(poly[k]), 2.0, color);
+            }
+        }
+    }
+
+    /// Draws a fading halo behind each point `constrain_to_bounds` last
+    /// moved.
+    fn draw_bounds_flash(&self, graphics: &mut Graphics2D) {
+        let elapsed = self.bounds_flash_start.elapsed();
+        if self.bounds_flash.is_empty() || elapsed >= BOUNDS_FLASH {
+            return;
+        }
+        let fade = 1.0 - elapsed.as_secs_f32() / BOUNDS_FLASH.as_secs_f32();
+        let color = Color::from_rgba(1.0, 0.6, 0.1, 0.8 * fade);
+        for p in self.bounds_flash.iter().filter_map(|&i| self.control_points.get(i)) {
+            graphics.draw_circle(Vector2::<f32>::from(*p), BOUNDS_FLASH_RADIUS, color);
+        }
+    }
+
+    /// While a Shift-drag is constraining a point, draws the allowed
+    /// direction through the anchor point.
+    fn draw_snap_guide(&self, graphics: &mut Graphics2D) {
+        let Some(i) = self.dragging.filter(|_| self.modifiers.shift()) else {
+            return;
+        };
+        let (Some(anchor), Some(&p)) = (self.snap_anchor(i), self.control_points.get(i)) else {
+            return;
+        };
+        let len = dist2(anchor, p).sqrt();
+        if len < f32::EPSILON {
+            return;
+        }
+        let reach = self.width.hypot(self.height) / len;
+        let dir = Vector2::new(p.x - anchor.x, p.y - anchor.y) * reach;
+        let anchor = Vector2::<f32>::from(anchor);
+        graphics.draw_line(anchor - dir, anchor + dir, 1.0, Color::from_rgb(0.6, 0.5, 0.2));
+    }
+
+    /// Draws the guide dashed and muted, with the dash pattern running on
+    /// across vertices so short edges still read as dashed.
+    fn draw_guide(&self, graphics: &mut Graphics2D) {
+        let Some(guide) = self.guide.as_ref().filter(|_| self.settings.show_guide) else {
+            return;
+        };
+        let color = Color::from_rgb(0.4, 0.4, 0.5);
+        for (points, closed) in &guide.paths {
+            let mut along = 0.0;
+            for (a, b) in edges(points, *closed) {
+                let len = dist2(a, b).sqrt();
+                let at = |t: f32| Vector2::new(a.x + (b.x - a.x) * t / len, a.y + (b.y - a.y) * t / len);
+                let mut t = 0.0;
+                while t < len {
+                    let phase = (along + t) % GUIDE_DASH_PERIOD;
+                    let in_dash = phase < GUIDE_DASH;
+                    let step = if in_dash { GUIDE_DASH - phase } else { GUIDE_DASH_PERIOD - phase };
+                    let end = (t + step.max(0.5)).min(len);
+                    if in_dash {
+                        graphics.draw_line(at(t), at(end), 1.0, color);
+                    }
+                    t = end;
+                }
+                along += len;
+            }
+        }
+    }
+
+    fn draw_mirror_axis(&self, graphics: &mut Graphics2D) {
+        if !self.settings.mirror {
+            return;
+        }
+        let (a, b) = match self.settings.mirror_axis {
+            MirrorAxis::Vertical => (Vector2::new(self.width / 2.0, 0.0), Vector2::new(self.width / 2.0, self.height)),
+            MirrorAxis::Horizontal => (Vector2::new(0.0, self.height / 2.0), Vector2::new(self.width, self.height / 2.0)),
+        };
+        graphics.draw_line(a, b, 1.0, Color::from_rgb(0.25, 0.35, 0.6));
+    }
+
+    fn draw_intersections(&self, graphics: &mut Graphics2D) {
+        let poly = self.control_polygon();
+        let n = poly.len();
+        let color = Color::from_rgb(1.0, 0.3, 0.3);
+        for &(i, j) in &self.intersections {
+            for e in [i, j] {
+                graphics.draw_line(Vector2::from(poly[e]), Vector2::from(poly[(e + 1) % n]), 2.0, color);
+            }
+        }
+    }
+
+    /// Draws a ring per control point, scaled by the square root of its
+    /// weight so heavier points look bigger.
+    fn draw_markers(&self, graphics: &mut Graphics2D) {
+        for (i, (p, w)) in self.control_points.iter().zip(&self.weights).enumerate() {
+            let center: Vector2<f32> = (*p).into();
+            let ring = if self.selected == Some(i) {
+                Color::YELLOW
+            } else {
+                Color::RED
+            };
+            let scale = w.sqrt();
+            graphics.draw_circle(center, POINT_OUTER_R * scale, ring);
+            graphics.draw_circle(center, POINT_INNER_R * scale, Color::from_rgb(0.12, 0.12, 0.12));
+        }
+    }
+
+    /// Multiplies the selected point's weight by `factor`, within
+    /// `WEIGHT_MIN..=WEIGHT_MAX`. The repeated point of a closed curve
+    /// shares point 0's weight.
+    fn scale_selected_weight(&mut self, factor: f32) {
+        let Some(i) = self.selected.filter(|&i| i < self.weights.len()) else {
+            return;
+        };
+        let w = (self.weights[i] * factor).clamp(WEIGHT_MIN, WEIGHT_MAX);
+        let last = self.weights.len() - 1;
+        if self.is_closed() && (i == 0 || i == last) {
+            self.weights[0] = w;
+            self.weights[last] = w;
+        } else {
+            self.weights[i] = w;
+        }
+        self.recompute_cache();
+    }
+
+    fn draw_label(&self, graphics: &mut Graphics2D, p: Pt, text: &str) {
+        let block = self.font.layout_text(text, HUD_FONT_SIZE, TextOptions::new());
+        let pos = Vector2::<f32>::from(p) + LABEL_OFFSET;
+        graphics.draw_text(pos, Color::from_rgb(0.9, 0.9, 0.9), &block);
+    }
+
+    /// Draws the point names, then (if enabled) every control point's
+    /// rounded position, nudging labels apart where they would overlap.
+    /// Names sit above right of their marker and coordinates below right,
+    /// and names are placed first so they keep their spot when crowded.
+    fn draw_annotations(&self, graphics: &mut Graphics2D) {
+        let editing = self.text_entry.as_ref().map(|e| e.target);
+        let mut labels = Vec::new();
+        for (i, (p, label)) in self.control_points.iter().zip(&self.labels).enumerate() {
+            if editing == Some(EntryTarget::Label(i)) {
+                continue;
+            }
+            if let Some(text) = label {
+                let block = self.font.layout_text(text, HUD_FONT_SIZE, TextOptions::new());
+                labels.push((block, Vector2::<f32>::from(*p) + LABEL_OFFSET, Color::from_rgb(0.9, 0.9, 0.9)));
+            }
+        }
+        if self.settings.show_coordinates {
+            for p in self.control_polygon() {
+                let text = format!("({}, {})", p.x.round(), p.y.round());
+                let block = self.font.layout_text(&text, COORD_FONT_SIZE, TextOptions::new());
+                labels.push((block, Vector2::<f32>::from(*p) + COORD_LABEL_OFFSET, Color::from_rgb(0.6, 0.6, 0.6)));
+            }
+        }
+
+        let boxes: Vec<_> = labels
+            .iter()
+            .map(|(block, pos, _)| (*pos, Vector2::new(block.width(), block.height())))
+            .collect();
+        for ((block, _, color), pos) in labels.iter().zip(place_labels(&boxes)) {
+            graphics.draw_text(pos, *color, block);
+        }
+    }
+
+    fn draw_text_entry(&self, graphics: &mut Graphics2D) {
+        let Some(entry) = &self.text_entry else {
+            return;
+        };
+        match entry.target {
+            EntryTarget::Label(i) => {
+                if let Some(p) = self.control_points.get(i) {
+                    self.draw_label(graphics, *p, &format!("{}_", entry.text));
+                }
+            }
+            EntryTarget::Point => {
+                let text = format!("add point (x y): {}_", entry.text);
+                let block = self.font.layout_text(&text, HUD_FONT_SIZE, TextOptions::new());
+                let pos = (HUD_MARGIN, self.height - HUD_MARGIN - block.height());
+                graphics.draw_text(pos, Color::from_rgb(0.9, 0.9, 0.9), &block);
+            }
+        }
+    }
+
+    /// Starts or pauses the animation, or resumes it after stepping. Needs
+    /// two points; with fewer there is nothing to animate.
+    fn toggle_animation(&mut self) {
+        if self.control_points.len() < 2 {
+            return;
+        }
+        if self.anim_held {
+            // resume from the level that was stepped to
+            self.anim_held = false;
+        } else {
+            self.anim_running = !self.anim_running;
+            self.anim_step = 0;
+        }
+        self.action_log.record(Action::Animation(self.anim_running));
+        self.last_anim_instant = Instant::now();
+    }
+
+    /// Picks the level for this frame: the pinned one, the held one, or the
+    /// next once the current level's time is up.
+    fn advance_animation(&mut self) {
+        let frozen = self.anim_running && self.settings.freeze_while_dragging && self.dragging.is_some();
+        if let Some(level) = self.settings.pinned_level {
+            self.anim_step = level.min(self.max_steps);
+        } else if frozen || self.anim_held {
+            // hold the current level; the interval restarts once the drag ends
+            self.last_anim_instant = Instant::now();
+        } else if self.anim_running && self.control_points.len() >= 2 && self.last_anim_instant.elapsed() >= self.level_durations[self.anim_step] {
+            self.last_anim_instant = Instant::now();
+            self.anim_step = (self.anim_step + 1) % (self.max_steps + 1);
+        } else if !self.anim_running {
+            self.anim_step = 0;
+        }
+    }
+
+    /// Shows the level `delta` away from the current one, holding the
+    /// animation there. A pinned level moves with it.
+    fn step_level(&mut self, delta: isize) {
+        if self.control_points.len() < 2 {
+            return;
+        }
+        let level = self.anim_step.saturating_add_signed(delta).min(self.max_steps);
+        if let Some(pinned) = &mut self.settings.pinned_level {
+            *pinned = level;
+        }
+        self.anim_step = level;
+        if !self.anim_held {
+            self.anim_running = true;
+            self.anim_held = true;
+            self.action_log.record(Action::Animation(false));
+        }
+    }
+
+    /// With no points there is nothing else on screen, so say how to start.
+    /// A single point only gets its marker; from two points on the curve
+    /// can be animated.
+    fn draw_empty_hint(&self, graphics: &mut Graphics2D) {
+        if !self.control_points.is_empty() || self.text_entry.is_some() {
+            return;
+        }
+        let block = self.font.layout_text(EMPTY_HINT, HUD_FONT_SIZE, TextOptions::new());
+        let pos = ((self.width - block.width()) / 2.0, (self.height - block.height()) / 2.0);
+        graphics.draw_text(pos, Color::from_rgb(0.5, 0.5, 0.5), &block);
+    }
+
+    fn start_label_entry(&mut self, trigger: char) {
+        if let Some(i) = self.selected.filter(|&i| i < self.control_points.len()) {
+            self.text_entry = Some(TextEntry {
+                target: EntryTarget::Label(i),
+                text: self.labels[i].clone().unwrap_or_default(),
+                swallow: Some(trigger),
+            });
+        }
+    }
+
+    fn start_point_entry(&mut self, trigger: char) {
+        if !self.closed {
+            self.text_entry = Some(TextEntry {
+                target: EntryTarget::Point,
+                text: String::new(),
+                swallow: Some(trigger),
+            });
+        }
+    }
+
+    /// Parses `x y` typed by the user. Anything else, including positions
+    /// outside the canvas, is rejected.
+    fn parse_typed_point(&self, text: &str) -> Option<Pt> {
+        let mut fields = text.split(|c: char| c.is_whitespace() || c == ',').filter(|f| !f.is_empty());
+        let (x, y) = (fields.next()?.parse::<f32>().ok()?, fields.next()?.parse::<f32>().ok()?);
+        let inside = (0.0..=self.width).contains(&x) && (0.0..=self.height).contains(&y);
+        (fields.next().is_none() && inside).then_some(Pt { x, y })
+    }
+
+    fn finish_text_entry(&mut self, entry: TextEntry) {
+        let text = entry.text.trim();
+        match entry.target {
+            EntryTarget::Label(i) => {
+                if let Some(label) = self.labels.get_mut(i) {
+                    *label = (!text.is_empty()).then(|| text.to_string());
+                }
+            }
+            EntryTarget::Point => match self.parse_typed_point(text) {
+                Some(p) => {
+                    self.push_point(p);
+                    self.recompute_cache();
+                    self.action_log.record(Action::Added(p));
+                }
+                None => println!("ignoring `{text}`: expected `x y` inside the canvas"),
+            },
+        }
+    }
+
+    /// Handles a key while text entry is active. Returns `false` when no
+    /// entry is in progress so the key falls through to the shortcuts.
+    fn text_entry_key(&mut self, key: Option<VirtualKeyCode>) -> bool {
+        let Some(entry) = &mut self.text_entry else {
+            return false;
+        };
+        match key {
+            Some(VirtualKeyCode::Return | VirtualKeyCode::NumpadEnter) => {
+                let entry = self.text_entry.take().unwrap();
+                self.finish_text_entry(entry);
+            }
+            Some(VirtualKeyCode::Escape) => self.text_entry = None,
+            Some(VirtualKeyCode::Backspace) => {
+                entry.text.pop();
+            }
+            _ => {}
+        }
+        true
+    }
+
+    fn draw_comparison(&self, graphics: &mut Graphics2D) {
+        let Some(cmp) = self.comparison.as_ref().filter(|c| c.visible) else {
+            return;
+        };
+        let Some(poly) = cmp.iters.get(self.anim_step).or(cmp.iters.last()) else {
+            return;
+        };
+
+        let color = Color::from_rgb(1.0, 0.45, 0.1);
+        for w in poly.windows(2) {
+            self.draw_segment(graphics, w[0], w[1], 1.0, color);
+        }
+        if cmp.closed && poly.len() >= 3 {
+            self.draw_segment(graphics, *poly.last().unwrap(), poly[0], 1.0, color);
+        }
+    }
+
+    fn draw_dot(&self, graphics: &mut Graphics2D, p: Pt) {
+        let center: Vector2<f32> = p.into();
+        graphics.draw_circle(center, self.settings.dot_radius, Color::GREEN);
+    }
+
+}
+
+impl WindowHandler for App {
+    fn on_draw(&mut self, helper: &mut WindowHelper, graphics: &mut Graphics2D) {
+        self.advance_animation();
+
+        if self.settings.breathing {
+            self.recompute_cache();
+        }
+
+        let full = self.displayed_level();
+        let to_draw = decimate(full, self.settings.point_budget);
+        let decimated = matches!(to_draw, Cow::Owned(_));
+
         graphics.clear_screen(Color::from_rgb(0.07, 0.07, 0.07));
+        self.draw_guide(graphics);
 
-        let closed_detected = self.control_points.len() >= 3
-            && dist2(self.control_points[0], *self.control_points.last().unwrap())
-                <= CLICK_RADIUS * CLICK_RADIUS;
+        let closed_detected = self.is_closed();
 
-        if self.control_points.len() >= 3 {
+        if self.control_points.len() >= 2 {
             let iter = self.control_points.windows(2);
             for w in iter {
                 self.draw_line(graphics, w[0], w[1], 1.0, false);
@@ -192,29 +2340,74 @@ impl WindowHandler for App {
             }
         }
 
-        if to_draw.len() >= 2 {
-            for w in to_draw.windows(2) {
-                self.draw_line(graphics, w[0], w[1], 1.0, true);
+        if self.settings.onion_skin && self.control_points.len() >= 2 {
+            self.draw_onion_skin(graphics, closed_detected);
+        } else if self.settings.dots {
+            // the finished curve rather than the animated level, so the dots
+            // hold still and stay up while paused
+            for p in decimate(self.finished_level(), self.settings.point_budget).iter() {
+                self.draw_dot(graphics, *p);
             }
-            if closed_detected {
-                self.draw_line(graphics, *to_draw.last().unwrap(), to_draw[0], 2.0, true);
+        } else if to_draw.len() >= 2 {
+            let style = if closed_detected { self.settings.curve_style } else { CurveStyle::Outline };
+            if style.filled() {
+                self.draw_fill(graphics, &to_draw);
+            }
+            if style.outlined() {
+                let colors = self.settings.gradient.then(|| arc_length_colors(&to_draw, closed_detected));
+                let color = |i: usize| colors.as_ref().map_or(Color::GREEN, |c| c[i.min(c.len() - 1)]);
+                for (i, w) in to_draw.windows(2).enumerate() {
+                    self.draw_segment(graphics, w[0], w[1], 1.0, color(i));
+                }
+                if closed_detected {
+                    self.draw_segment(graphics, *to_draw.last().unwrap(), to_draw[0], 2.0, color(to_draw.len() - 1));
+                }
+                self.draw_joins(graphics, &to_draw, closed_detected, 1.0, colors.as_deref());
             }
         }
 
-        for p in &self.control_points {
-            let center: Vector2<f32> = (*p).into();
-            graphics.draw_circle(center, POINT_OUTER_R, Color::RED);
-            graphics.draw_circle(center, POINT_INNER_R, Color::from_rgb(0.12, 0.12, 0.12));
+        self.draw_other_curves(graphics);
+        self.draw_comparison(graphics);
+
+        // markers, labels and the control polygon can be left out of the
+        // animation for clean recordings
+        let show_markers = !self.anim_running || self.settings.markers_during_anim;
+        if show_markers {
+            self.draw_direction_arrows(graphics);
+        }
+        self.draw_mirror_axis(graphics);
+        self.draw_snap_guide(graphics);
+        self.draw_ripple(graphics);
+        self.draw_tangent_polygon(graphics);
+        self.draw_intersections(graphics);
+        self.draw_bounds_flash(graphics);
+        if show_markers {
+            self.draw_markers(graphics);
+            self.draw_annotations(graphics);
         }
+        self.draw_text_entry(graphics);
+        self.draw_empty_hint(graphics);
+
+        self.draw_hud(graphics, full, closed_detected, decimated);
 
+        if let Some(interval) = self.settings.frame_pacing.frame_interval() {
+            if let Some(wait) = interval.checked_sub(self.last_frame_instant.elapsed()) {
+                std::thread::sleep(wait);
+            }
+        }
+        self.last_frame_instant = Instant::now();
         helper.request_redraw();
     }
 
     fn on_mouse_move(&mut self, _helper: &mut WindowHelper, position: Vector2<f32>) {
         self.last_mouse_pos = position;
         if let Some(idx) = self.dragging {
-            if let Some(p) = self.control_points.get_mut(idx) {
-                *p = Self::mouse_pos_to_pt(position);
+            let mut pt = self.mouse_pos_to_pt(position);
+            if let Some(anchor) = self.snap_anchor(idx).filter(|_| self.modifiers.shift()) {
+                pt = self.mouse_pos_to_pt(snap_to_angle(anchor, pt, self.settings.snap_angle).into());
+            }
+            if idx < self.control_points.len() {
+                self.move_point(idx, pt);
                 self.recompute_cache();
             } else {
                 self.dragging = None;
@@ -223,35 +2416,48 @@ impl WindowHandler for App {
     }
 
     fn on_mouse_button_down(&mut self, _helper: &mut WindowHelper, button: MouseButton) {
-    let pt = Self::mouse_pos_to_pt(self.last_mouse_pos);
+    let pt = self.mouse_pos_to_pt(self.last_mouse_pos);
     match button {
         MouseButton::Right => {
             self.dragging = self.find_point_index_near(pt, CLICK_RADIUS);
+            self.selected = self.dragging;
         }
         
         MouseButton::Left => {
-            
+            if self.settings.snap_to_curve && self.insert_on_curve(pt) {
+                self.recompute_cache();
+                return;
+            }
+
             let mut near = false;
             for i in 0..self.control_points.len() {
                 if dist2(pt, self.control_points[i]) < 250.0 {
                     near = true;
                 }
             }
-            if self.control_points.len() >= 3 {
+            if self.settings.mirror && !self.closed {
+                if !near {
+                    self.add_mirrored(pt);
+                }
+            } else if self.control_points.len() >= 3 {
                 // check if clicked near the first point
                 if dist2(pt, self.control_points[0]) <= CLICK_RADIUS * CLICK_RADIUS {
                     // push the *first point* again to close the shape
-                    self.control_points.push(self.control_points[0]);
+                    self.push_point(self.control_points[0]);
                     self.closed = true;
+                    self.action_log.record(Action::Closed);
                 } else if !near && !self.closed {
                     // normal add
-                    self.control_points.push(pt);
+                    self.push_point(pt);
+                    self.action_log.record(Action::Added(pt));
                 }
-            } else if self.control_points.len() > 0 && !near && !self.closed {
+            } else if !self.control_points.is_empty() && !near && !self.closed {
                 // check if less than 3 points and if clicked near 
-                self.control_points.push(pt);
-            } else if self.control_points.len() == 0 {
-                self.control_points.push(pt);
+                self.push_point(pt);
+                self.action_log.record(Action::Added(pt));
+            } else if self.control_points.is_empty() {
+                self.push_point(pt);
+                self.action_log.record(Action::Added(pt));
             }
             self.recompute_cache();
         }
@@ -261,28 +2467,576 @@ impl WindowHandler for App {
 
     fn on_mouse_button_up(&mut self, _helper: &mut WindowHelper, button: MouseButton) {
         if button == MouseButton::Right {
-            self.dragging = None;
+            // drags are logged once, where they end
+            if let Some(index) = self.dragging.take() {
+                if let Some(&to) = self.control_points.get(index) {
+                    self.action_log.record(Action::Moved { index, to });
+                }
+            }
         }
     }
 
-    fn on_key_down(&mut self, _helper: &mut WindowHelper, key: Option<VirtualKeyCode>, _scancode: u32) {
+    fn on_mouse_wheel_scroll(&mut self, _helper: &mut WindowHelper, distance: MouseScrollDistance) {
+        let y = match distance {
+            MouseScrollDistance::Lines { y, .. } => y,
+            MouseScrollDistance::Pixels { y, .. } => y / 40.0,
+            MouseScrollDistance::Pages { y, .. } => y * 10.0,
+        };
+        if y != 0.0 {
+            self.scale_selected_weight(WEIGHT_FACTOR.powf(y as f32));
+        }
+    }
+
+    fn on_keyboard_modifiers_changed(&mut self, _helper: &mut WindowHelper, state: ModifiersState) {
+        self.modifiers = state;
+    }
+
+    fn on_keyboard_char(&mut self, _helper: &mut WindowHelper, c: char) {
+        if let Some(entry) = &mut self.text_entry {
+            if entry.swallow.take().is_some_and(|t| t.eq_ignore_ascii_case(&c)) || c.is_control() {
+                return;
+            }
+            entry.text.push(c);
+        }
+    }
+
+    fn on_key_down(&mut self, helper: &mut WindowHelper, key: Option<VirtualKeyCode>, _scancode: u32) {
+        if self.text_entry_key(key) {
+            return;
+        }
+
         match key {
             Some(VirtualKeyCode::Escape) => std::process::exit(0),
-            Some(VirtualKeyCode::Return | VirtualKeyCode::NumpadEnter) if !self.control_points.is_empty() => {
-                self.anim_running = !self.anim_running;
-                if self.anim_running {
-                    self.anim_step = 0;
-                    self.last_anim_instant = Instant::now();
+            Some(VirtualKeyCode::S) if self.modifiers.ctrl() => self.save_curve(Path::new(CURVE_FILE)),
+            Some(VirtualKeyCode::S) => self.split_at_selected(),
+            Some(VirtualKeyCode::Tab) => self.cycle_curves(),
+            Some(VirtualKeyCode::J) => self.join_nearest(),
+            Some(VirtualKeyCode::O) if self.modifiers.ctrl() => self.open_curve(Path::new(CURVE_FILE)),
+            Some(VirtualKeyCode::K) if self.modifiers.ctrl() => self.load_comparison(Path::new(CURVE_FILE)),
+            Some(VirtualKeyCode::K) => {
+                if let Some(cmp) = &mut self.comparison {
+                    cmp.visible = !cmp.visible;
                 }
             }
-            Some(VirtualKeyCode::C) => {
-                self.control_points.clear();
-                self.recompute_cache();
-                self.anim_running = false;
-                self.closed = false;
-                self.anim_step = 0;
+            Some(VirtualKeyCode::Return | VirtualKeyCode::NumpadEnter) => self.toggle_animation(),
+            Some(VirtualKeyCode::Left) => {
+                self.step_level(-1);
+                helper.request_redraw();
+            }
+            Some(VirtualKeyCode::Right) => {
+                self.step_level(1);
+                helper.request_redraw();
+            }
+            Some(VirtualKeyCode::N) => self.start_label_entry('n'),
+            Some(VirtualKeyCode::R) if self.modifiers.ctrl() => self.reset_settings(),
+            Some(VirtualKeyCode::R) => self.reflect_to_closed_loop(),
+            Some(VirtualKeyCode::T) => self.start_point_entry('t'),
+            Some(VirtualKeyCode::Z) if self.modifiers.ctrl() => self.undo_clear(),
+            Some(VirtualKeyCode::X) => self.settings.show_tangent_polygon = !self.settings.show_tangent_polygon,
+            Some(VirtualKeyCode::V) => self.settings.snap_to_curve = !self.settings.snap_to_curve,
+            Some(VirtualKeyCode::U) => self.settings.markers_during_anim = !self.settings.markers_during_anim,
+            Some(VirtualKeyCode::A) => self.settings.show_arrows = !self.settings.show_arrows,
+            Some(VirtualKeyCode::C) => self.clear(),
+            Some(VirtualKeyCode::Z) => self.settings.freeze_while_dragging = !self.settings.freeze_while_dragging,
+            Some(VirtualKeyCode::B) if self.modifiers.ctrl() => {
+                self.set_mode(|s| s.constrain_to_bounds = !s.constrain_to_bounds)
+            }
+            Some(VirtualKeyCode::B) => self.set_mode(|s| s.breathing = !s.breathing),
+            Some(VirtualKeyCode::D) if self.modifiers.ctrl() => self.duplicate_point(),
+            Some(VirtualKeyCode::D) => self.settings.dots = !self.settings.dots,
+            Some(VirtualKeyCode::Minus | VirtualKeyCode::NumpadSubtract) => self.scale_selected_weight(1.0 / WEIGHT_FACTOR),
+            Some(VirtualKeyCode::Equals | VirtualKeyCode::NumpadAdd) => self.scale_selected_weight(WEIGHT_FACTOR),
+            Some(VirtualKeyCode::LBracket) => {
+                self.settings.dot_radius = (self.settings.dot_radius - DOT_RADIUS_STEP).max(DOT_RADIUS_MIN);
+            }
+            Some(VirtualKeyCode::RBracket) => {
+                self.settings.dot_radius = (self.settings.dot_radius + DOT_RADIUS_STEP).min(DOT_RADIUS_MAX);
+            }
+            Some(VirtualKeyCode::M) => self.set_mode(|s| s.smooth_mode = s.smooth_mode.next()),
+            Some(VirtualKeyCode::E) => self.set_mode(|s| s.skip_short_edges = !s.skip_short_edges),
+            Some(VirtualKeyCode::Comma) => {
+                self.set_mode(|s| s.min_edge_len = (s.min_edge_len - MIN_EDGE_LEN_STEP).max(MIN_EDGE_LEN_MIN));
+            }
+            Some(VirtualKeyCode::Period) => {
+                self.set_mode(|s| s.min_edge_len = (s.min_edge_len + MIN_EDGE_LEN_STEP).min(MIN_EDGE_LEN_MAX));
+            }
+            Some(VirtualKeyCode::G) if self.modifiers.ctrl() => {
+                self.settings.export.control_polygon = !self.settings.export.control_polygon;
             }
+            Some(VirtualKeyCode::G) => {
+                self.settings.export.align_to_pixels = !self.settings.export.align_to_pixels;
+            }
+            Some(VirtualKeyCode::F) if self.modifiers.ctrl() => self.settings.curve_style = self.settings.curve_style.next(),
+            Some(VirtualKeyCode::F) => self.settings.frame_pacing = self.settings.frame_pacing.next(),
+            Some(VirtualKeyCode::Y) if self.modifiers.shift() => self.settings.mirror_axis = self.settings.mirror_axis.next(),
+            Some(VirtualKeyCode::Y) => self.settings.mirror = !self.settings.mirror,
+            Some(VirtualKeyCode::P) => {
+                self.settings.pinned_level = match self.settings.pinned_level {
+                    Some(_) => None,
+                    None => Some(self.anim_step),
+                };
+            }
+            Some(VirtualKeyCode::Q) => self.settings.gradient = !self.settings.gradient,
+            Some(VirtualKeyCode::W) => self.settings.line_join = self.settings.line_join.next(),
+            Some(VirtualKeyCode::O) => self.settings.onion_skin = !self.settings.onion_skin,
+            Some(VirtualKeyCode::I) => {
+                self.settings.detect_intersections = !self.settings.detect_intersections;
+                self.update_intersections();
+            }
+            Some(VirtualKeyCode::L) if self.modifiers.ctrl() => {
+                self.settings.ripple_preview = !self.settings.ripple_preview;
+            }
+            Some(VirtualKeyCode::L) => self.settings.show_coordinates = !self.settings.show_coordinates,
+            Some(VirtualKeyCode::H) if self.modifiers.ctrl() => self.set_mode(|s| s.end_handles = !s.end_handles),
+            Some(VirtualKeyCode::H) => self.settings.show_hud = !self.settings.show_hud,
+            Some(VirtualKeyCode::Key9) => {
+                self.settings.point_budget = (self.settings.point_budget / 2).max(POINT_BUDGET_MIN);
+            }
+            Some(VirtualKeyCode::Key0) => {
+                self.settings.point_budget = (self.settings.point_budget * 2).min(POINT_BUDGET_MAX);
+            }
+            Some(VirtualKeyCode::F5) => self.export(ExportFormat::Svg),
+            Some(VirtualKeyCode::F6) if self.modifiers.shift() => {
+                self.export_sprite_sheet(Path::new(SPRITE_SHEET_FILE))
+            }
+            Some(VirtualKeyCode::F6) => self.export(ExportFormat::Png),
+            Some(VirtualKeyCode::F7) => self.export(ExportFormat::Obj),
+            Some(VirtualKeyCode::F8) if self.modifiers.shift() => self.settings.show_guide = !self.settings.show_guide,
+            Some(VirtualKeyCode::F8) => self.load_guide(),
+            Some(VirtualKeyCode::F9) => self.dump_action_log(Path::new(ACTION_LOG_FILE)),
             _ => {}
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn xy(points: &[Pt]) -> Vec<(f32, f32)> {
+        points.iter().map(|p| (p.x, p.y)).collect()
+    }
+
+    fn pts(coords: &[(f32, f32)]) -> Vec<Pt> {
+        coords.iter().map(|&(x, y)| Pt { x, y }).collect()
+    }
+
+    fn app_with(coords: &[(f32, f32)]) -> App {
+        let mut app = App::with_config(&Config::default());
+        for p in pts(coords) {
+            app.push_point(p);
+        }
+        app.recompute_cache();
+        app
+    }
+
+    const SQUARE: [(f32, f32); 4] = [(100.0, 100.0), (300.0, 100.0), (300.0, 300.0), (100.0, 300.0)];
+    const WAVE: [(f32, f32); 5] = [(100.0, 400.0), (250.0, 200.0), (400.0, 400.0), (550.0, 200.0), (700.0, 400.0)];
+
+    #[test]
+    fn min_edge_step_keeps_short_edges_of_open_curve() {
+        let points = pts(&[(0.0, 0.0), (100.0, 0.0), (104.0, 0.0), (104.0, 100.0)]);
+        let out = chaikin_step_min_edge(&points, false, 12.0);
+        assert_eq!(
+            xy(&out),
+            [
+                (0.0, 0.0),
+                (25.0, 0.0),
+                (75.0, 0.0),
+                (100.0, 0.0),
+                (104.0, 0.0),
+                (104.0, 25.0),
+                (104.0, 75.0),
+                (104.0, 100.0),
+            ]
+        );
+    }
+
+    #[test]
+    fn min_edge_step_does_not_repeat_first_point_on_short_wrap_edge() {
+        let points = pts(&[(0.0, 0.0), (4.0, 0.0), (100.0, 50.0), (0.0, 4.0)]);
+        let out = chaikin_step_min_edge(&points, true, 12.0);
+        assert_eq!(
+            xy(&out),
+            [
+                (0.0, 0.0),
+                (4.0, 0.0),
+                (28.0, 12.5),
+                (76.0, 37.5),
+                (75.0, 38.5),
+                (25.0, 15.5),
+                (0.0, 4.0),
+            ]
+        );
+    }
+
+    #[test]
+    fn set_mode_refreshes_cached_levels() {
+        let mut app = app_with(&SQUARE);
+        let chaikin = xy(&app.cached_iters[1]);
+        app.set_mode(|s| s.smooth_mode = s.smooth_mode.next());
+        assert_ne!(app.settings.smooth_mode, SmoothMode::Chaikin);
+        assert_ne!(xy(&app.cached_iters[1]), chaikin);
+        assert_eq!(
+            xy(&app.cached_iters[1]),
+            xy(&precompute_iterations(&pts(&SQUARE), &[], 1, false, &app.subdivision())[1])
+        );
+    }
+
+    #[test]
+    fn undo_clear_restores_points_labels_weights_and_closed_state() {
+        let mut app = app_with(&SQUARE);
+        app.labels[1] = Some("corner".to_string());
+        app.weights[2] = 3.0;
+        app.closed = true;
+        let points = xy(&app.control_points);
+
+        app.clear();
+        assert!(app.control_points.is_empty());
+        assert!(!app.closed);
+
+        app.undo_clear();
+        assert_eq!(xy(&app.control_points), points);
+        assert_eq!(app.labels, [None, Some("corner".to_string()), None, None]);
+        assert_eq!(app.weights, [1.0, 1.0, 3.0, 1.0]);
+        assert!(app.closed);
+        assert_eq!(app.cached_iters.len(), app.max_steps + 1);
+    }
+
+    #[test]
+    fn sample_at_open_polyline() {
+        let poly = pts(&[(0.0, 0.0), (10.0, 0.0), (10.0, 10.0)]);
+        assert_eq!(xy(&[sample_at(&poly, 0.0, false)]), [(0.0, 0.0)]);
+        assert_eq!(xy(&[sample_at(&poly, 0.5, false)]), [(10.0, 0.0)]);
+        assert_eq!(xy(&[sample_at(&poly, 1.0, false)]), [(10.0, 10.0)]);
+        assert_eq!(xy(&[sample_at(&poly, 2.0, false)]), [(10.0, 10.0)]);
+    }
+
+    #[test]
+    fn sample_at_closed_polyline_wraps_to_the_start() {
+        let poly = pts(&[(0.0, 0.0), (10.0, 0.0), (10.0, 10.0), (0.0, 10.0)]);
+        assert_eq!(xy(&[sample_at(&poly, 0.0, true)]), [(0.0, 0.0)]);
+        assert_eq!(xy(&[sample_at(&poly, 0.5, true)]), [(10.0, 10.0)]);
+        assert_eq!(xy(&[sample_at(&poly, 1.0, true)]), [(0.0, 0.0)]);
+
+        // two points have no closing edge
+        let poly = pts(&[(0.0, 0.0), (10.0, 0.0)]);
+        assert_eq!(xy(&[sample_at(&poly, 1.0, true)]), [(10.0, 0.0)]);
+    }
+
+    #[test]
+    fn svg_path_data() {
+        let poly = pts(&[(0.0, 0.0), (10.0, 0.0), (10.5, -2.25)]);
+        assert_eq!(to_svg_path(&poly[..2], true), "M 0 0 L 10 0 Z");
+        assert_eq!(to_svg_path(&poly, false), "M 0 0 L 10 0 L 10.5 -2.25");
+        assert_eq!(to_svg_path(&[], true), "");
+    }
+
+    #[test]
+    fn cubic_step_point_counts() {
+        let points = pts(&SQUARE);
+        for n in 2..=4 {
+            assert_eq!(cubic_step(&points[..n], false).len(), 2 * n - 1);
+        }
+        for n in 3..=4 {
+            assert_eq!(cubic_step(&points[..n], true).len(), 2 * n);
+        }
+
+        // open curves keep their endpoints
+        let open = cubic_step(&points, false);
+        assert_eq!(xy(&[open[0], open[open.len() - 1]]), [SQUARE[0], SQUARE[3]]);
+    }
+
+    #[test]
+    fn reset_settings_keeps_points() {
+        let mut app = app_with(&SQUARE);
+        let defaults = app.default_settings.clone();
+        app.set_mode(|s| {
+            s.smooth_mode = s.smooth_mode.next();
+            s.skip_short_edges = !s.skip_short_edges;
+            s.min_edge_len += MIN_EDGE_LEN_STEP;
+        });
+        app.settings.dots = !app.settings.dots;
+        app.settings.show_hud = !app.settings.show_hud;
+
+        app.reset_settings();
+        assert_eq!(app.settings.smooth_mode, defaults.smooth_mode);
+        assert_eq!(app.settings.skip_short_edges, defaults.skip_short_edges);
+        assert_eq!(app.settings.min_edge_len, defaults.min_edge_len);
+        assert_eq!(app.settings.dots, defaults.dots);
+        assert_eq!(app.settings.show_hud, defaults.show_hud);
+        assert_eq!(xy(&app.control_points), SQUARE);
+        assert_eq!(xy(&app.cached_iters[1]), xy(&app_with(&SQUARE).cached_iters[1]));
+    }
+
+    #[test]
+    fn tiny_inputs_always_have_a_level_to_show() {
+        for n in 0..=2 {
+            let mut app = app_with(&SQUARE[..n]);
+            app.level_durations = vec![Duration::ZERO; app.max_steps + 1];
+            assert_eq!(app.cached_iters.len(), app.max_steps + 1);
+            assert_eq!(app.displayed_level().is_empty(), n == 0);
+
+            app.toggle_animation();
+            assert_eq!(app.anim_running, n == 2);
+            for _ in 0..=app.max_steps + 1 {
+                app.advance_animation();
+                let level = app.displayed_level();
+                assert_eq!(level.is_empty(), n == 0);
+                // two points stay on their straight segment
+                assert!(level.iter().all(|p| p.y == SQUARE[0].1));
+            }
+
+            app.step_level(1);
+            app.step_level(-3);
+            assert_eq!(app.displayed_level().is_empty(), n == 0);
+            assert_eq!(app.anim_held, n == 2);
+        }
+    }
+
+    #[test]
+    fn mirror_pairs_survive_unmirrored_inserts() {
+        let mut app = app_with(&[]);
+        app.settings.mirror = true;
+        app.add_mirrored(Pt { x: 100.0, y: 100.0 });
+        app.add_mirrored(Pt { x: 200.0, y: 300.0 });
+        assert_eq!(xy(&app.control_points), [(100.0, 100.0), (200.0, 300.0), (660.0, 300.0), (760.0, 100.0)]);
+
+        // a typed point has no partner and must not be dragged along
+        app.push_point(Pt { x: 400.0, y: 700.0 });
+        app.move_point(0, Pt { x: 120.0, y: 140.0 });
+        assert_eq!(
+            xy(&app.control_points),
+            [(120.0, 140.0), (200.0, 300.0), (660.0, 300.0), (740.0, 140.0), (400.0, 700.0)]
+        );
+        app.move_point(4, Pt { x: 410.0, y: 690.0 });
+        assert_eq!(xy(&app.control_points[..4]), [(120.0, 140.0), (200.0, 300.0), (660.0, 300.0), (740.0, 140.0)]);
+
+        // pairs still hold after a point is inserted between them
+        app.insert_point(2, Pt { x: 430.0, y: 500.0 });
+        app.move_point(1, Pt { x: 210.0, y: 310.0 });
+        assert_eq!(xy(&[app.control_points[3]]), [(650.0, 310.0)]);
+    }
+
+    #[test]
+    fn split_open_curve_into_halves_sharing_the_point() {
+        let mut app = app_with(&WAVE);
+        app.selected = Some(2);
+        app.split_at_selected();
+        assert_eq!(xy(&app.control_points), WAVE[..=2]);
+        assert_eq!(app.other_curves.len(), 1);
+        let tail = &app.other_curves[0];
+        assert_eq!(xy(&tail.points), WAVE[2..]);
+
+        let mut rebuilt = app.control_points.clone();
+        rebuilt.extend(&tail.points[1..]);
+        assert_eq!(xy(&rebuilt), WAVE);
+    }
+
+    #[test]
+    fn split_closed_curve_opens_it_at_the_point() {
+        let mut app = app_with(&SQUARE);
+        app.push_point(app.control_points[0]);
+        app.closed = true;
+        app.selected = Some(1);
+        app.split_at_selected();
+        assert!(!app.closed);
+        assert!(!app.is_closed());
+        assert!(app.other_curves.is_empty());
+        assert_eq!(xy(&app.control_points), [SQUARE[1], SQUARE[2], SQUARE[3], SQUARE[0]]);
+        assert_eq!(app.labels.len(), 4);
+        assert_eq!(app.weights.len(), 4);
+    }
+
+    #[test]
+    fn join_after_split_round_trips() {
+        let mut app = app_with(&WAVE);
+        app.labels[2] = Some("middle".to_string());
+        app.labels[4] = Some("end".to_string());
+        app.weights[3] = 2.0;
+        app.selected = Some(2);
+        app.split_at_selected();
+
+        app.join_nearest();
+        assert!(app.other_curves.is_empty());
+        assert!(app.other_iters.is_empty());
+        assert_eq!(xy(&app.control_points), WAVE);
+        assert_eq!(
+            app.labels,
+            [None, None, Some("middle".to_string()), None, Some("end".to_string())]
+        );
+        assert_eq!(app.weights, [1.0, 1.0, 1.0, 2.0, 1.0]);
+        assert_eq!(app.mirror_pairs.len(), WAVE.len());
+    }
+
+    fn assert_evenly_spaced(samples: &[Pt], closed: bool) {
+        let gaps: Vec<f32> = edges(samples, closed).map(|(a, b)| dist2(a, b).sqrt()).collect();
+        let (min, max) = gaps.iter().fold((f32::MAX, 0.0f32), |(lo, hi), &g| (lo.min(g), hi.max(g)));
+        assert!(max / min < 1.1, "gaps range from {min} to {max}");
+    }
+
+    #[test]
+    fn curve_samples_are_evenly_spaced() {
+        let app = app_with(&WAVE);
+        let samples = app.curve_samples(32);
+        assert_eq!(samples.len(), 32);
+        assert_eq!(xy(&[samples[0], samples[31]]), [WAVE[0], WAVE[4]]);
+        assert_evenly_spaced(&samples, false);
+
+        let mut app = app_with(&SQUARE);
+        app.push_point(app.control_points[0]);
+        app.closed = true;
+        app.recompute_cache();
+        let samples = app.curve_samples(32);
+        assert_eq!(samples.len(), 32);
+        assert_evenly_spaced(&samples, true);
+
+        assert!(app_with(&[]).curve_samples(8).is_empty());
+    }
+
+    fn assert_close(a: &[Pt], b: &[Pt]) {
+        assert_eq!(a.len(), b.len());
+        for (p, q) in a.iter().zip(b) {
+            assert!(dist2(*p, *q) < 1e-6, "{p:?} != {q:?}");
+        }
+    }
+
+    #[test]
+    fn uniform_weights_match_classic_chaikin() {
+        let scheme = Subdivision::default();
+        for (base, closed) in [(pts(&WAVE), false), (pts(&SQUARE), true)] {
+            let classic = precompute_iterations(&base, &[], 4, closed, &scheme);
+            let weighted = precompute_iterations(&base, &vec![2.0; base.len()], 4, closed, &scheme);
+            for (a, b) in weighted.iter().zip(&classic) {
+                assert_close(a, b);
+            }
+            assert_close(&classic[1], &chaikin_step(&base, closed));
+        }
+    }
+
+    #[test]
+    fn grab_prefers_nearest_then_topmost() {
+        let app = app_with(&[(400.0, 400.0), (104.0, 100.0), (300.0, 300.0), (104.0, 100.0), (100.0, 100.0)]);
+        assert_eq!(app.find_point_index_near(Pt { x: 101.0, y: 100.0 }, CLICK_RADIUS), Some(4));
+        // points 1 and 3 coincide; the later one is drawn on top
+        assert_eq!(app.find_point_index_near(Pt { x: 103.0, y: 100.0 }, CLICK_RADIUS), Some(3));
+        assert_eq!(app.find_point_index_near(Pt { x: 200.0, y: 200.0 }, CLICK_RADIUS), None);
+    }
+
+    #[test]
+    fn grab_on_closed_curve_start_picks_point_zero() {
+        let mut app = app_with(&SQUARE);
+        app.push_point(app.control_points[0]);
+        app.closed = true;
+        assert_eq!(app.find_point_index_near(Pt { x: 100.0, y: 100.0 }, CLICK_RADIUS), Some(0));
+
+        app.move_point(0, Pt { x: 80.0, y: 90.0 });
+        assert_eq!(xy(&[app.control_points[0], app.control_points[4]]), [(80.0, 90.0), (80.0, 90.0)]);
+        assert!(app.is_closed());
+    }
+
+    #[test]
+    fn constrain_to_bounds_pulls_opened_points_inside() {
+        let path = std::env::temp_dir().join(format!("chaikin_{}_bounds.txt", std::process::id()));
+        std::fs::write(&path, "-50 100\n400 400\n2000 -30\n300 5000\n").unwrap();
+        let mut app = app_with(&[]);
+        app.settings.constrain_to_bounds = true;
+        app.open_curve(&path);
+        let _ = std::fs::remove_file(&path);
+
+        assert_eq!(
+            xy(&app.control_points),
+            [(0.0, 100.0), (400.0, 400.0), (app.width, 0.0), (300.0, app.height)]
+        );
+        assert_eq!(app.bounds_flash, [0, 2, 3]);
+        assert!(app.cached_iters[app.max_steps]
+            .iter()
+            .all(|p| (0.0..=app.width).contains(&p.x) && (0.0..=app.height).contains(&p.y)));
+    }
+
+    fn closed_square() -> App {
+        let mut app = app_with(&SQUARE);
+        app.push_point(app.control_points[0]);
+        app.closed = true;
+        app.recompute_cache();
+        app
+    }
+
+    #[test]
+    fn snapped_point_goes_into_the_span_it_refines() {
+        let mut app = app_with(&WAVE);
+        assert!(app.insert_on_curve(Pt { x: 325.0, y: 290.0 }));
+        assert_eq!(app.control_points.len(), 6);
+        let s = app.control_points[2];
+        assert!(s.x > 250.0 && s.x < 400.0, "{s:?}");
+        assert_eq!(xy(&[app.control_points[1], app.control_points[3]]), [WAVE[1], WAVE[2]]);
+        assert_eq!((app.labels.len(), app.weights.len(), app.mirror_pairs.len()), (6, 6, 6));
+
+        // it lies on the curve it was snapped to
+        let before = app_with(&WAVE);
+        let curve = before.cached_iters.last().unwrap();
+        let on_curve = edges(curve, false)
+            .map(|(a, b)| dist2(closest_point_on_segment(s, a, b), s))
+            .fold(f32::MAX, f32::min);
+        assert!(on_curve < 1e-3);
+    }
+
+    #[test]
+    fn snapped_point_refines_closed_curves_including_the_closing_span() {
+        let mut app = closed_square();
+        assert!(app.insert_on_curve(Pt { x: 200.0, y: 310.0 }));
+        assert_eq!(xy(&app.control_points[2..5]), [SQUARE[2], (200.0, 300.0), SQUARE[3]]);
+
+        let mut app = closed_square();
+        assert!(app.insert_on_curve(Pt { x: 95.0, y: 200.0 }));
+        assert_eq!(xy(&app.control_points[3..]), [SQUARE[3], (100.0, 200.0), SQUARE[0]]);
+        assert!(app.is_closed());
+
+        // next to an existing point nothing is added
+        assert!(!app.insert_on_curve(Pt { x: 98.0, y: 205.0 }));
+        assert_eq!(app.control_points.len(), 6);
+    }
+
+    #[test]
+    fn breathing_never_closes_an_open_curve_with_near_ends() {
+        let mut app = app_with(&[(100.0, 100.0), (400.0, 100.0), (400.0, 400.0), (100.0, 400.0), (100.0, 114.0)]);
+        app.set_mode(|s| s.breathing = true);
+        let now = Instant::now();
+        // sweep most of a breathing cycle so the ends pass close together
+        for k in 0..40 {
+            app.breath_start = now.checked_sub(Duration::from_millis(k * 100)).unwrap_or(now);
+            app.recompute_cache();
+            assert!(!app.is_closed());
+            assert_eq!(app.cached_iters[0].len(), 5);
+            for level in 0..=app.max_steps {
+                let mask = app.ripple_mask(4, level).expect("Chaikin has local support");
+                assert_eq!(mask.len(), app.cached_iters[level].len());
+            }
+        }
+    }
+
+    fn open_text(name: &str, text: &str) -> App {
+        let path = std::env::temp_dir().join(format!("chaikin_{}_{name}.txt", std::process::id()));
+        std::fs::write(&path, text).unwrap();
+        let mut app = app_with(&[]);
+        app.open_curve(&path);
+        let _ = std::fs::remove_file(&path);
+        app
+    }
+
+    #[test]
+    fn opened_curve_closure_matches_the_file() {
+        let app = open_text("repeat", "100 100\n300 100\n300 300\n100 300\n100 100\n");
+        assert!(app.is_closed());
+        assert_eq!(app.control_points.len(), 5);
+        assert_eq!(app.cached_iters[0].len(), 4);
+
+        let app = open_text("near", "100 100\n300 100\n300 300\n100 300\n103 104\n");
+        assert!(app.is_closed());
+        assert_eq!(xy(&app.cached_iters[0]), SQUARE);
+
+        let app = open_text("short", "100 100\n300 100\n100 100\n");
+        assert!(!app.is_closed());
+        assert_eq!(app.cached_iters[0].len(), 3);
+    }
+}
@@ -1,15 +1,127 @@
 use chaikin::*;
+use speedy2d::window::{WindowCreationOptions, WindowPosition, WindowSize};
 use speedy2d::Window;
 
+const USAGE: &str = "\
+usage: chaikin [options]
+
+Defaults are read from chaikin.toml in the working directory or in
+$XDG_CONFIG_HOME/chaikin (~/.config/chaikin). Flags override the file.
+
+options:
+  --width PX           window width (860)
+  --height PX          window height (800)
+  --max-steps N        subdivision levels, at most 12 (7)
+  --interval MS        time per animation step (1100)
+  --level-durations L  per-level times in ms, e.g. 2000,1500,1100
+  --point-budget N     points drawn before decimating (4096)
+  --min-edge PX        short-edge threshold (12)
+  --scheme NAME        chaikin or cubic (chaikin)
+  --snap-angle DEG     angle step for Shift-drags (15)
+  --breath-amplitude PX
+                       breathing offset (6)
+  --breath-frequency HZ
+                       breathing cycles per second (0.25)
+  --sheet-columns N    tiles per row in sprite sheets (4)
+  --export-scale N     render PNG exports at N times the canvas size, 1 to 4 (1)
+  --pin-level N        always show and export level N
+  --no-vsync           disable vsync (config: vsync = false)
+  --bench              time the subdivision code, print CSV and exit
+  -h, --help           show this help
+
+mouse:
+  left-click           add a point (click the first point to close)
+  right-drag           move a point (also selects it)
+  Shift+right-drag     keep the edge to the previous point at a multiple
+                       of the snap angle
+
+keys:
+  Enter                start/pause animation (resume after stepping)
+  Left, Right          step one level down or up and hold there
+  B                    breathing: the curve gently undulates (points stay put)
+  Ctrl+B               keep control points inside the canvas after every edit
+  D, [ ]               dots at the deepest level's vertices, dot size
+  - =, wheel           selected point weight (heavier pulls the curve closer)
+  Ctrl+D               duplicate the selected or hovered point (sharper corner)
+  M                    smoothing scheme (chaikin/cubic)
+  E, , .               skip short edges, short-edge threshold
+  O                    onion skin (all levels)
+  Z                    freeze the animation while dragging
+  I                    self-intersection check
+  A                    control polygon with direction arrows
+  X                    tangent polygon (control edge midpoints)
+  U                    hide markers and control polygon while animating
+  H, 9 0               HUD, point budget
+  Ctrl+H               first and last points of an open curve become tangent
+                       handles: the curve runs from the second to the
+                       next-to-last point, leaving and arriving along them
+  F                    frame pacing (uncapped/60/30/15 fps)
+  P                    pin the current level (shown and exported), unpin
+  Q                    rainbow gradient along the curve
+  Ctrl+F               closed curves: outline, filled, filled with outline
+  W                    round or butt line joins (round is faked with discs)
+  N                    name the selected point
+  V                    new points go onto the curve, refining it where clicked
+  Y, Shift+Y           live mirror (adds and drags come in pairs), axis
+  T                    type a point as `x y`, Enter to add
+  L                    label points with their coordinates
+  Ctrl+L               while dragging, highlight the part of the curve the
+                       point affects (not with short-edge skipping)
+  S                    split the curve at the selected point (opens a
+                       closed curve there instead)
+  Tab                  switch to the next curve
+  J                    join the nearest open curve onto this one
+  R                    reflect an open curve into a closed loop
+  G                    pixel-align exports
+  Ctrl+G               add the control polygon to SVG exports (own group)
+  F5, F6, F7           export SVG, PNG, OBJ (always the full canvas)
+  Shift+F6             export every level as a PNG sprite sheet
+  Ctrl+S, Ctrl+O       save, open chaikin_curve.txt
+  F8, Shift+F8         load a tracing guide from chaikin_guide.svg or
+                       chaikin_guide.json, show/hide it
+  F9                   write the action log to chaikin_actions.log
+  Ctrl+K, K            compare with the saved curve, toggle comparison
+  C, Ctrl+Z            clear, undo the last clear
+  Ctrl+R               reset all settings (keeps the points)
+  Esc                  quit
+";
+
 fn main() {
-    
-    let window = Window::new_centered(
-        "Chaikin: Left-click --> add | drag with Right-click --> move | Enter --> start/pause | C --> clear | Esc --> quit",
-        (WIDTH as u32, HEIGHT as u32),
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if args.iter().any(|a| a == "-h" || a == "--help") {
+        print!("{USAGE}");
+        return;
+    }
+    if args.iter().any(|a| a == "--bench") {
+        if let Err(e) = run_benchmarks(&mut std::io::stdout().lock()) {
+            eprintln!("chaikin: {e}");
+            std::process::exit(1);
+        }
+        return;
+    }
+
+    let config = match Config::load(args) {
+        Ok(config) => config,
+        Err(e) => {
+            eprintln!("chaikin: {e}\n\n{USAGE}");
+            std::process::exit(2);
+        }
+    };
+
+    // vsync can only be chosen at window creation; frame pacing is runtime (F)
+    let options = WindowCreationOptions::new_windowed(
+        WindowSize::PhysicalPixels((config.width as u32, config.height as u32).into()),
+        Some(WindowPosition::Center),
+    )
+    .with_vsync(config.vsync);
+
+    let window = Window::new_with_options(
+        "Chaikin: Left-click --> add | drag with Right-click --> move | Enter --> start/pause | C --> clear | Esc --> quit (--help for all keys)",
+        options,
     )
     .unwrap();
 
-    let app = App::new();
+    let app = App::with_config(&config);
 
     // run the event loop
     window.run_loop(app);
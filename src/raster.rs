@@ -0,0 +1,75 @@
+//! Minimal software rasterizer used by the image exporters.
+
+use crate::{closest_point_on_segment, dist2, Pt};
+
+pub(crate) type Rgb = [u8; 3];
+
+/// An RGBA8 pixel buffer that shapes can be drawn onto.
+pub(crate) struct Canvas {
+    width: u32,
+    height: u32,
+    pixels: Vec<u8>,
+}
+
+impl Canvas {
+    pub(crate) fn new(width: u32, height: u32, background: Rgb) -> Self {
+        let mut pixels = Vec::with_capacity(width as usize * height as usize * 4);
+        for _ in 0..width as usize * height as usize {
+            pixels.extend_from_slice(&[background[0], background[1], background[2], 255]);
+        }
+        Self { width, height, pixels }
+    }
+
+    pub(crate) fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub(crate) fn height(&self) -> u32 {
+        self.height
+    }
+
+    pub(crate) fn pixels(&self) -> &[u8] {
+        &self.pixels
+    }
+
+    fn blend(&mut self, x: u32, y: u32, color: Rgb, coverage: f32) {
+        let i = (y as usize * self.width as usize + x as usize) * 4;
+        for (dst, src) in self.pixels[i..i + 3].iter_mut().zip(color) {
+            let d = *dst as f32;
+            *dst = (d + (src as f32 - d) * coverage).round() as u8;
+        }
+    }
+
+    /// Calls `coverage` for every pixel centre within `pad` of the box spanned
+    /// by `min` and `max`, blending `color` by the returned amount.
+    fn fill_with(&mut self, min: Pt, max: Pt, pad: f32, color: Rgb, coverage: impl Fn(Pt) -> f32) {
+        let x0 = (min.x - pad).floor().max(0.0) as u32;
+        let y0 = (min.y - pad).floor().max(0.0) as u32;
+        let x1 = ((max.x + pad).ceil().max(0.0) as u32).min(self.width);
+        let y1 = ((max.y + pad).ceil().max(0.0) as u32).min(self.height);
+
+        for y in y0..y1 {
+            for x in x0..x1 {
+                let c = coverage(Pt {
+                    x: x as f32 + 0.5,
+                    y: y as f32 + 0.5,
+                });
+                if c > 0.0 {
+                    self.blend(x, y, color, c.min(1.0));
+                }
+            }
+        }
+    }
+
+    /// Draws an antialiased line of the given thickness from `a` to `b`.
+    pub(crate) fn draw_line(&mut self, a: Pt, b: Pt, thickness: f32, color: Rgb) {
+        let half = thickness * 0.5;
+        let min = Pt { x: a.x.min(b.x), y: a.y.min(b.y) };
+        let max = Pt { x: a.x.max(b.x), y: a.y.max(b.y) };
+        self.fill_with(min, max, half + 1.0, color, |p| half + 0.5 - dist_to_segment(p, a, b));
+    }
+}
+
+fn dist_to_segment(p: Pt, a: Pt, b: Pt) -> f32 {
+    dist2(p, closest_point_on_segment(p, a, b)).sqrt()
+}